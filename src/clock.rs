@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Source of time for the mediator and vote systems, so tests can control it.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn unix_now(&self) -> u64;
+    fn unix_now_ms(&self) -> u64;
+    // Like thread::park_timeout, this may return early when the thread is unparked.
+    fn park_timeout(&self, d: Duration);
+
+    fn sleep(&self, d: Duration) {
+        let deadline = self.now() + d;
+
+        loop {
+            let now = self.now();
+            if now >= deadline {
+                break;
+            }
+
+            self.park_timeout(deadline - now);
+        }
+    }
+
+    fn elapsed(&self, since: Instant) -> Duration {
+        self.now()
+            .checked_duration_since(since)
+            .unwrap_or(Duration::from_secs(0))
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct SystemClock {}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs()
+    }
+
+    fn unix_now_ms(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_millis() as u64
+    }
+
+    fn park_timeout(&self, d: Duration) {
+        thread::park_timeout(d);
+    }
+}
+
+#[cfg(test)]
+pub use manual::ManualClock;
+
+#[cfg(test)]
+mod manual {
+    use super::Clock;
+
+    use std::sync::{Condvar, Mutex};
+    use std::thread::{self, Thread};
+    use std::time::{Duration, Instant};
+
+    struct ManualState {
+        elapsed: Duration,
+        sleepers: Vec<(Instant, Thread)>,
+    }
+
+    // A clock that only moves when told to. Threads parked on it wake up once it's
+    // advanced past their deadline, or when they're unparked.
+    pub struct ManualClock {
+        start: Instant,
+        state: Mutex<ManualState>,
+        sleepers_changed: Condvar,
+    }
+
+    impl ManualClock {
+        pub fn new() -> Self {
+            ManualClock {
+                start: Instant::now(),
+                state: Mutex::new(ManualState {
+                    elapsed: Duration::from_secs(0),
+                    sleepers: Vec::new(),
+                }),
+                sleepers_changed: Condvar::new(),
+            }
+        }
+
+        pub fn advance(&self, d: Duration) {
+            let mut state = self.state.lock().unwrap();
+            state.elapsed += d;
+
+            let now = self.start + state.elapsed;
+            for (deadline, thread) in &state.sleepers {
+                if *deadline <= now {
+                    thread.unpark();
+                }
+            }
+        }
+
+        // Blocks until `n` threads are parked on the clock waiting for a future deadline, so
+        // advancing it is deterministic.
+        pub fn wait_for_sleepers(&self, n: usize) {
+            let mut state = self.state.lock().unwrap();
+
+            loop {
+                let now = self.start + state.elapsed;
+                let waiting = state
+                    .sleepers
+                    .iter()
+                    .filter(|(deadline, _)| *deadline > now)
+                    .count();
+
+                if waiting >= n {
+                    break;
+                }
+
+                state = self.sleepers_changed.wait(state).unwrap();
+            }
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start + self.state.lock().unwrap().elapsed
+        }
+
+        fn unix_now(&self) -> u64 {
+            self.state.lock().unwrap().elapsed.as_secs()
+        }
+
+        fn unix_now_ms(&self) -> u64 {
+            self.state.lock().unwrap().elapsed.as_millis() as u64
+        }
+
+        fn park_timeout(&self, d: Duration) {
+            let id = thread::current().id();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                let deadline = self.start + state.elapsed + d;
+
+                state.sleepers.push((deadline, thread::current()));
+                self.sleepers_changed.notify_all();
+            }
+
+            thread::park();
+
+            let mut state = self.state.lock().unwrap();
+            state.sleepers.retain(|(_, thread)| thread.id() != id);
+            self.sleepers_changed.notify_all();
+        }
+    }
+}
@@ -1,8 +1,10 @@
 extern crate serde;
-use crate::vote_system::VoteSystem;
-use serde::Serialize;
+use crate::modifier::Modifier;
+use crate::vote_system::{VoteSystem, VoteSystemRegistry};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all(deserialize = "snake_case"))]
 pub enum Button {
     Up,
     Down,
@@ -17,6 +19,19 @@ pub enum Button {
 }
 
 impl Button {
+    pub const ALL: [Button; 10] = [
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::A,
+        Button::B,
+        Button::Select,
+        Button::Start,
+        Button::L,
+        Button::R,
+    ];
+
     pub fn from_string(s: String) -> Option<Self> {
         use Button::*;
 
@@ -41,19 +56,59 @@ impl Button {
 pub enum Command {
     ChangeVoteSystem(VoteSystem),
     Action(Button),
+    Ballot(Vec<Button>),
+    Join,
+    Team(String),
+    Modifier(Modifier),
 }
 
 impl Command {
-    pub fn from_string(s: String) -> Option<Self> {
+    pub fn from_string(s: String, registry: &VoteSystemRegistry) -> Option<Self> {
         use Command::*;
 
-        match s.as_str() {
-            "anarchy" => Some(ChangeVoteSystem(VoteSystem::Anarchy)),
-            "democracy" => Some(ChangeVoteSystem(VoteSystem::Democracy)),
-            _ => match Button::from_string(s) {
+        if s == "!join" {
+            return Some(Join);
+        }
+
+        if let Some(team) = s.strip_prefix("!team ") {
+            return Some(Team(team.trim().to_owned()));
+        }
+
+        if let Some(modifier) = s
+            .strip_prefix('!')
+            .and_then(crate::modifier::Modifier::from_string)
+        {
+            return Some(Modifier(modifier));
+        }
+
+        if s.split_whitespace().nth(1).is_some() {
+            return Self::ballot_from_string(&s);
+        }
+
+        match registry.by_keyword(&s) {
+            Some(system) => Some(ChangeVoteSystem(system)),
+            None => match Button::from_string(s) {
                 Some(button) => Some(Action(button)),
                 None => None,
             },
         }
     }
+
+    // "a b up" lists several buttons, in order of preference.
+    fn ballot_from_string(s: &str) -> Option<Self> {
+        let buttons: Option<Vec<Button>> = s
+            .split_whitespace()
+            .map(|token| Button::from_string(token.to_owned()))
+            .collect();
+
+        buttons.map(Command::Ballot)
+    }
+
+    // Only approval and ranked choice take ballots, everywhere else "a b" does nothing.
+    pub fn action(&self) -> Option<Button> {
+        match self {
+            Command::Action(button) => Some(*button),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,281 @@
+use super::{ChatHandler, Chatter, CommandInput, Input, Recorder};
+use crate::mediator::MediatorControlSender;
+use crate::moderation::{ModerationConfig, Role};
+use crate::vote_system::VoteSystemRegistry;
+use serde::Deserialize;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Error as WebSocketError, HandshakeError, Message};
+
+// Inputs are short, anything longer than this closes the connection.
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+// Clients that take longer to ask for a WebSocket are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// What local tools send, as JSON.
+#[derive(Debug, Deserialize)]
+pub struct InputMessage {
+    pub user: String,
+    // Anything a chatter could type, like "a" or "!pause".
+    pub command: String,
+}
+
+// Compares every byte, so how long it takes doesn't tell how much of the token was right.
+pub fn tokens_match(expected: &str, given: &str) -> bool {
+    let (expected, given) = (expected.as_bytes(), given.as_bytes());
+
+    !expected.is_empty()
+        && expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// Hands inputs posted to the HTTP server over to the input.
+#[derive(Clone)]
+pub struct HttpInputSender {
+    token: String,
+    handler: Arc<Mutex<ChatHandler>>,
+}
+
+impl HttpInputSender {
+    pub fn authorizes(&self, token: &str) -> bool {
+        tokens_match(&self.token, token)
+    }
+
+    // Whether the message was a command, or a control. Tools can send any name, so
+    // everyone is a viewer.
+    pub fn send(&self, message: &InputMessage) -> bool {
+        let handler = self.handler.lock().unwrap();
+
+        handler.handle(&message.command, Chatter::new(&message.user), Role::Viewer)
+    }
+}
+
+// Reads inputs sent by local tools, like a web controller or a bridge to another chat,
+// through the HTTP server and, if set up, WebSockets.
+pub struct HttpInput {
+    sender: HttpInputSender,
+    websocket: Option<TcpListener>,
+    rx_input: Mutex<Option<Receiver<Input>>>,
+}
+
+impl HttpInput {
+    pub fn new(
+        token: String,
+        websocket_address: Option<String>,
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        recorder: Option<Recorder>,
+    ) -> io::Result<Self> {
+        let (tx_input, rx_input) = channel();
+        let handler = ChatHandler {
+            name: "http_input",
+            registry,
+            tx_control,
+            moderation,
+            tx_input,
+            recorder,
+        };
+        let websocket = match websocket_address {
+            Some(address) => Some(TcpListener::bind(address)?),
+            None => None,
+        };
+
+        Ok(HttpInput {
+            sender: HttpInputSender {
+                token,
+                handler: Arc::new(Mutex::new(handler)),
+            },
+            websocket,
+            rx_input: Mutex::new(Some(rx_input)),
+        })
+    }
+
+    pub fn sender(&self) -> HttpInputSender {
+        self.sender.clone()
+    }
+
+    // From the query string, or an "Authorization: Bearer" header.
+    fn request_token(request: &Request) -> Option<String> {
+        request
+            .uri()
+            .query()
+            .and_then(|query| {
+                query
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("token="))
+            })
+            .or_else(|| {
+                request
+                    .headers()
+                    .get("authorization")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "))
+            })
+            .map(|token| token.trim().to_owned())
+    }
+
+    // The errors are tungstenite's own, large as they are.
+    #[allow(clippy::result_large_err)]
+    fn serve_websocket(stream: TcpStream, sender: &HttpInputSender) -> Result<(), WebSocketError> {
+        stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .map_err(WebSocketError::Io)?;
+
+        let check_token =
+            |request: &Request, response: Response| match HttpInput::request_token(request) {
+                Some(ref token) if sender.authorizes(token) => Ok(response),
+                _ => {
+                    let mut refusal = ErrorResponse::new(None);
+                    *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+                    Err(refusal)
+                }
+            };
+        let config = WebSocketConfig {
+            max_message_size: Some(MAX_MESSAGE_BYTES),
+            max_frame_size: Some(MAX_MESSAGE_BYTES),
+            ..WebSocketConfig::default()
+        };
+        let mut websocket = tungstenite::accept_hdr_with_config(stream, check_token, Some(config))
+            .map_err(|e| match e {
+                HandshakeError::Failure(e) => e,
+                // Only a timeout stops a blocking stream midway.
+                HandshakeError::Interrupted(_) => {
+                    WebSocketError::Io(io::ErrorKind::TimedOut.into())
+                }
+            })?;
+        websocket
+            .get_ref()
+            .set_read_timeout(None)
+            .map_err(WebSocketError::Io)?;
+
+        // Pings are answered, and closes completed, by the next read.
+        loop {
+            let text = match websocket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Binary(data)) => String::from_utf8_lossy(&data).into_owned(),
+                Ok(_) => continue,
+                Err(WebSocketError::ConnectionClosed) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            match serde_json::from_str::<InputMessage>(&text) {
+                Ok(message) => {
+                    sender.send(&message);
+                }
+                Err(e) => println!("http_input: ignoring {:?}, {}.", text, e),
+            }
+        }
+    }
+}
+
+impl CommandInput for HttpInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        if let Some(ref listener) = self.websocket {
+            let listener = listener.try_clone().unwrap();
+            let sender = self.sender();
+
+            thread::spawn(move || {
+                for stream in listener.incoming().filter_map(Result::ok) {
+                    let sender = sender.clone();
+
+                    thread::spawn(move || {
+                        if let Err(e) = HttpInput::serve_websocket(stream, &sender) {
+                            println!("http_input: WebSocket closed, {}.", e);
+                        }
+                    });
+                }
+            });
+        }
+
+        self.rx_input.lock().unwrap().take().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Button, Command};
+    use tungstenite::client::IntoClientRequest;
+
+    #[test]
+    fn matches_only_the_whole_token() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secret", "secreT"));
+        assert!(!tokens_match("secret", "secret2"));
+        assert!(!tokens_match("secret", ""));
+        assert!(!tokens_match("", ""));
+    }
+
+    #[test]
+    fn takes_inputs_through_websockets_with_the_token() {
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let (tx_control, rx_control) = channel();
+        let address = "127.0.0.1:0".to_owned();
+        let input = HttpInput::new(
+            "secret".to_owned(),
+            Some(address),
+            registry,
+            tx_control,
+            ModerationConfig::default(),
+            None,
+        )
+        .unwrap();
+        let address = input
+            .websocket
+            .as_ref()
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let rx_input = input.create_receiver();
+
+        match tungstenite::connect(format!("ws://{}/?token=wrong", address)) {
+            Err(WebSocketError::Http(response)) => {
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED)
+            }
+            _ => panic!("connected with the wrong token"),
+        }
+
+        let mut request = format!("ws://{}/", address).into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        let (mut websocket, _) = tungstenite::connect(request).unwrap();
+
+        // The longest a control frame can be, which the pong must echo whole.
+        let ping = vec![b'x'; 125];
+        websocket.send(Message::Ping(ping.clone())).unwrap();
+        assert_eq!(websocket.read().unwrap(), Message::Pong(ping));
+
+        websocket.send(Message::text("not json")).unwrap();
+        websocket
+            .send(Message::text(r#"{"user": "someone", "command": "!pause"}"#))
+            .unwrap();
+        websocket
+            .send(Message::text(r#"{"user": "someone", "command": "a"}"#))
+            .unwrap();
+        assert_eq!(
+            rx_input.recv().unwrap(),
+            Input(Command::Action(Button::A), Chatter::new("someone"))
+        );
+        // Whoever sends through a tool is a viewer.
+        assert!(rx_control.try_recv().is_err());
+
+        websocket
+            .send(Message::Binary(vec![b'x'; MAX_MESSAGE_BYTES + 1]))
+            .unwrap();
+        assert!(websocket.read().is_err());
+    }
+}
@@ -0,0 +1,805 @@
+use super::{ChatHandler, Chatter, CommandInput, ConnectionState, Input, Recorder};
+use crate::logging::logs_inputs;
+use crate::mediator::{MediatorControl, MediatorControlSender};
+use crate::moderation::{ModerationConfig, Role};
+use crate::vote_system::VoteSystemRegistry;
+use irc::client::data::{AccessLevel, User};
+use irc::client::prelude::{Client, Command as IrcCommand, Config, IrcClient, Message};
+use irc::error::IrcError;
+use irc::proto::message::Tag;
+use irc::proto::CapSubCommand;
+use serde::Deserialize;
+use std::cmp;
+use std::default::Default;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConnectionConfig {
+    // Waits this long before reconnecting, doubling after each failed attempt.
+    pub backoff_initial_ms: u64,
+    pub backoff_max_ms: u64,
+    // Pings the server this often, and reconnects when it doesn't answer in time.
+    pub ping_secs: u32,
+    pub ping_timeout_secs: u32,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        ConnectionConfig {
+            backoff_initial_ms: 1000,
+            backoff_max_ms: 60_000,
+            ping_secs: 60,
+            ping_timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct IrcConfig {
+    pub server: String,
+    pub port: u16,
+    pub use_tls: bool,
+    pub nickname: String,
+    pub password: Option<String>,
+    // Logs in to the nickname's account with SASL, instead of sending the password as PASS.
+    pub sasl: bool,
+    pub channels: Vec<String>,
+}
+
+impl Default for IrcConfig {
+    fn default() -> Self {
+        IrcConfig {
+            server: "localhost".to_owned(),
+            port: 6667,
+            use_tls: false,
+            nickname: "tpp_rs".to_owned(),
+            password: None,
+            sasl: false,
+            channels: Vec::new(),
+        }
+    }
+}
+
+// Reads inputs from the channels of any IRC server.
+pub struct IrcInput {
+    irc_config: Config,
+    // The SASL password, if logging in that way.
+    sasl_password: Option<String>,
+    // Twitch's chat is IRC, with a few extensions.
+    twitch: bool,
+    // The source bans from the chat service are scoped to, when there are several.
+    source: Option<String>,
+    connection: ConnectionConfig,
+    registry: Arc<VoteSystemRegistry>,
+    tx_control: MediatorControlSender,
+    moderation: ModerationConfig,
+    tx_state: Sender<ConnectionState>,
+    rx_state: Mutex<Option<Receiver<ConnectionState>>>,
+    recorder: Option<Recorder>,
+}
+
+impl IrcInput {
+    pub fn new(
+        config: IrcConfig,
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        connection: ConnectionConfig,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        let (password, sasl_password) = if config.sasl {
+            (None, config.password)
+        } else {
+            (config.password, None)
+        };
+        let channels = config
+            .channels
+            .iter()
+            .map(|channel| format!("#{}", channel.trim_start_matches('#')))
+            .collect();
+
+        let irc_config = Config {
+            nickname: Some(config.nickname),
+            server: Some(config.server),
+            port: Some(config.port),
+            password,
+            channels: Some(channels),
+            use_ssl: Some(config.use_tls),
+            ..Default::default()
+        };
+
+        IrcInput {
+            sasl_password,
+            ..Self::with_irc_config(
+                irc_config, connection, registry, tx_control, moderation, recorder,
+            )
+        }
+    }
+
+    pub(super) fn with_irc_config(
+        irc_config: Config,
+        connection: ConnectionConfig,
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        let (tx_state, rx_state) = channel();
+        let irc_config = Config {
+            ping_time: Some(connection.ping_secs),
+            ping_timeout: Some(connection.ping_timeout_secs),
+            ..irc_config
+        };
+
+        IrcInput {
+            irc_config,
+            sasl_password: None,
+            twitch: false,
+            source: None,
+            connection,
+            registry,
+            tx_control,
+            moderation,
+            tx_state,
+            rx_state: Mutex::new(Some(rx_state)),
+            recorder,
+        }
+    }
+
+    // Asks for Twitch's extensions, and treats each channel's owner as its broadcaster.
+    pub(super) fn on_twitch(self) -> Self {
+        IrcInput {
+            twitch: true,
+            ..self
+        }
+    }
+
+    pub(super) fn for_source(self, source: &str) -> Self {
+        IrcInput {
+            source: Some(source.to_owned()),
+            ..self
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        if self.twitch {
+            "twitch_input"
+        } else {
+            "irc_input"
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn connect(irc_config: &Config, twitch: bool, sasl: bool) -> Result<IrcClient, IrcError> {
+        use irc::client::prelude::*;
+
+        let client = IrcClient::from_config(irc_config.clone())?;
+        if twitch {
+            // Tags tell who's who, commands bring Twitch's own messages, like RECONNECT and CLEARCHAT.
+            client.send_cap_req(&[
+                Capability::Custom("twitch.tv/tags"),
+                Capability::Custom("twitch.tv/commands"),
+            ])?;
+        } else {
+            // Tells which services account sent each message, see ModerationConfig::operators.
+            client.send_cap_req(&[Capability::AccountTag])?;
+        }
+
+        if sasl {
+            // Registration only ends after logging in, see authenticate.
+            client.send_cap_req(&[Capability::Sasl])?;
+            client.send(IrcCommand::NICK(irc_config.nickname()?.to_owned()))?;
+            client.send(IrcCommand::USER(
+                irc_config.username().to_owned(),
+                "0".to_owned(),
+                irc_config.real_name().to_owned(),
+            ))?;
+        } else {
+            client.identify()?;
+        }
+
+        Ok(client)
+    }
+
+    // Takes a step of the SASL PLAIN login, if the message asks for one. Gives why the
+    // server refused the login, if it did, since going on without it would be another user.
+    #[allow(clippy::result_large_err)]
+    fn authenticate(
+        client: &IrcClient,
+        message: &Message,
+        irc_config: &Config,
+        password: &str,
+    ) -> Result<Option<&'static str>, IrcError> {
+        use irc::client::prelude::*;
+
+        match message.command {
+            IrcCommand::CAP(_, CapSubCommand::ACK, _, Some(ref caps)) if caps.contains("sasl") => {
+                client.send_sasl_plain()?
+            }
+            IrcCommand::CAP(_, CapSubCommand::NAK, _, Some(ref caps)) if caps.contains("sasl") => {
+                return Ok(Some("the server doesn't support SASL"));
+            }
+            IrcCommand::AUTHENTICATE(ref data) if data == "+" => {
+                let account = irc_config.nickname()?;
+                let credentials = format!("{}\0{}\0{}", account, account, password);
+                client.send_sasl(base64::encode(&credentials))?
+            }
+            IrcCommand::Response(Response::RPL_SASLSUCCESS, _, _) => {
+                client.send(IrcCommand::CAP(None, CapSubCommand::END, None, None))?
+            }
+            IrcCommand::Response(Response::ERR_SASLFAIL, _, _)
+            | IrcCommand::Response(Response::ERR_SASLABORT, _, _) => {
+                return Ok(Some("the server refused the SASL login"));
+            }
+            _ => (),
+        }
+
+        Ok(None)
+    }
+
+    // What the chatter's status in the channel makes them, on servers other than Twitch.
+    fn channel_role(users: &[User], name: &str) -> Role {
+        let levels = users
+            .iter()
+            .find(|user| user.get_nickname().eq_ignore_ascii_case(name))
+            .map(|user| user.access_levels())
+            .unwrap_or_default();
+
+        if levels.contains(&AccessLevel::Owner) {
+            Role::Broadcaster
+        } else if levels.contains(&AccessLevel::Admin) || levels.contains(&AccessLevel::Oper) {
+            Role::Moderator
+        } else {
+            Role::Viewer
+        }
+    }
+
+    // Reads who sent a message from its IRCv3 tags. Missing tags are left empty.
+    fn chatter(name: &str, tags: &[Tag]) -> Chatter {
+        let tag = |key: &str| {
+            tags.iter()
+                .find(|Tag(k, _)| k == key)
+                .and_then(|Tag(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+        let flag = |key: &str| tag(key).as_deref() == Some("1");
+        let badges: Vec<String> = tag("badges")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|badge| badge.split('/').next())
+            .filter(|badge| !badge.is_empty())
+            .map(|badge| badge.to_owned())
+            .collect();
+
+        Chatter {
+            id: tag("user-id"),
+            display_name: tag("display-name"),
+            color: tag("color"),
+            moderator: flag("mod") || badges.iter().any(|b| b == "moderator"),
+            subscriber: flag("subscriber") || badges.iter().any(|b| b == "subscriber"),
+            vip: flag("vip") || badges.iter().any(|b| b == "vip"),
+            // "*" is how servers say the chatter isn't logged in.
+            account: tag("account")
+                .filter(|account| account != "*")
+                .map(|account| account.to_lowercase()),
+            message_id: tag("id"),
+            sent_at: tag("tmi-sent-ts").and_then(|ts| ts.parse().ok()),
+            badges,
+            ..Chatter::new(name)
+        }
+    }
+}
+
+// Reads chat messages out of everything the server sends.
+struct MessageHandler {
+    twitch: bool,
+    source: Option<String>,
+    chat: ChatHandler,
+}
+
+impl MessageHandler {
+    // The client, when there's one, tells who's an operator in each channel.
+    fn handle(&self, message: Message, client: Option<&IrcClient>) {
+        if logs_inputs() {
+            println!("{}: {:?}", self.chat.name, message);
+        }
+
+        match message.command {
+            IrcCommand::PRIVMSG(ref target, ref msg) => {
+                let user = message.source_nickname().unwrap_or("unknown user");
+                let tags = message.tags.clone().unwrap_or_default();
+                let mut chatter = IrcInput::chatter(user, &tags);
+                if self.twitch {
+                    chatter.account = Some(user.to_lowercase());
+                }
+
+                let role = if self.twitch {
+                    // On Twitch, each channel belongs to its streamer.
+                    if user.eq_ignore_ascii_case(target.trim_start_matches('#')) {
+                        Role::Broadcaster
+                    } else {
+                        self.chat.moderation.role_of(&chatter)
+                    }
+                } else {
+                    let users = client
+                        .and_then(|client| client.list_users(target))
+                        .unwrap_or_default();
+                    cmp::max(
+                        IrcInput::channel_role(&users, user),
+                        self.chat.moderation.role_of(&chatter),
+                    )
+                };
+
+                self.chat.handle(msg, chatter, role);
+            }
+            // A Twitch moderator timed someone out or banned them, so their inputs from this
+            // source go too, for the session. Clearing the whole chat comes without a user,
+            // and changes nothing.
+            IrcCommand::Raw(ref command, _, Some(ref user))
+                if self.twitch && command == "CLEARCHAT" =>
+            {
+                let secs = message
+                    .tags
+                    .iter()
+                    .flatten()
+                    .find(|Tag(key, _)| key == "ban-duration")
+                    .and_then(|Tag(_, value)| value.as_ref()?.parse().ok());
+                let user = match self.source {
+                    Some(ref source) => format!("{}:{}", source, user.to_lowercase()),
+                    None => user.to_lowercase(),
+                };
+                let control = MediatorControl::ChatBan(user, secs);
+
+                println!("{}: got {:?} from Twitch.", self.chat.name, control);
+                self.chat.tx_control.send(control).unwrap();
+            }
+            _ => (),
+        }
+    }
+}
+
+impl CommandInput for IrcInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        use irc::client::prelude::*;
+
+        let (tx, rx) = channel();
+        let name = self.name();
+        let twitch = self.twitch;
+        let irc_config = self.irc_config.clone();
+        let sasl_password = self.sasl_password.clone();
+        let connection = self.connection.clone();
+        let tx_state = self.tx_state.clone();
+        let handler = MessageHandler {
+            twitch,
+            source: self.source.clone(),
+            chat: ChatHandler {
+                name,
+                registry: self.registry.clone(),
+                tx_control: self.tx_control.clone(),
+                moderation: self.moderation.clone(),
+                tx_input: tx,
+                recorder: self.recorder.clone(),
+            },
+        };
+        thread::spawn(move || {
+            let mut backoff_ms = connection.backoff_initial_ms;
+
+            // Nobody may be listening to the connection state, so it's fine if sending fails.
+            loop {
+                let _ = tx_state.send(ConnectionState::Connecting);
+
+                let mut reconnect_asked = false;
+                let mut registered = false;
+                let mut refused = None;
+                // The irc crate's errors are large, so they're boxed on the way out.
+                let result = IrcInput::connect(&irc_config, twitch, sasl_password.is_some())
+                    .map_err(Box::new)
+                    .and_then(|client| {
+                        let _ = tx_state.send(ConnectionState::Connected);
+
+                        for message in client.stream().wait() {
+                            let message = message?;
+
+                            if let Some(ref password) = sasl_password {
+                                refused = IrcInput::authenticate(
+                                    &client,
+                                    &message,
+                                    &irc_config,
+                                    password,
+                                )?;
+                                if refused.is_some() {
+                                    break;
+                                }
+                            }
+
+                            match message.command {
+                                // Twitch asks for it before restarting a chat server.
+                                IrcCommand::Raw(ref command, _, _) if command == "RECONNECT" => {
+                                    reconnect_asked = true;
+                                    break;
+                                }
+                                IrcCommand::Response(Response::RPL_WELCOME, _, _) => {
+                                    registered = true;
+                                }
+                                _ => (),
+                            }
+
+                            handler.handle(message, Some(&client));
+                        }
+
+                        Ok(())
+                    });
+
+                // Only connections that got as far as logging in start the backoff over, so
+                // servers refusing the login aren't hammered.
+                if registered {
+                    backoff_ms = connection.backoff_initial_ms;
+                }
+                let reason = match (result, refused) {
+                    (_, Some(refusal)) => format!("couldn't log in, {}", refusal),
+                    (Ok(()), None) if reconnect_asked => "the server asked to reconnect".to_owned(),
+                    (Ok(()), None) => "the server closed the connection".to_owned(),
+                    (Err(e), None) => e.to_string(),
+                };
+                println!(
+                    "{}: disconnected, {}. Retrying in {}ms.",
+                    name, reason, backoff_ms
+                );
+                let _ = tx_state.send(ConnectionState::Disconnected {
+                    reason,
+                    retry_in_ms: backoff_ms,
+                });
+
+                thread::sleep(Duration::from_millis(backoff_ms));
+                backoff_ms = cmp::min(backoff_ms * 2, connection.backoff_max_ms);
+            }
+        });
+
+        rx
+    }
+
+    fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+        self.rx_state.lock().unwrap().take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Button, Command};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    // Takes a connection, and answers each line the client sends starting with a key with
+    // the lines given for it, until it has sent a line starting with `until`.
+    fn serve(
+        listener: &TcpListener,
+        replies: &[(&str, &[&str])],
+        until: &str,
+    ) -> (TcpStream, Vec<String>) {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream.try_clone().unwrap();
+        let mut received = Vec::new();
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_owned();
+
+            for (key, lines) in replies {
+                if line.starts_with(key) {
+                    for reply in lines.iter() {
+                        write!(writer, "{}\r\n", reply).unwrap();
+                    }
+                }
+            }
+
+            let done = line.starts_with(until);
+            received.push(line);
+            if done {
+                break;
+            }
+        }
+
+        (stream, received)
+    }
+
+    fn input(config: IrcConfig, connection: ConnectionConfig) -> IrcInput {
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let (tx_control, _rx_control) = channel();
+
+        IrcInput::new(
+            config,
+            registry,
+            tx_control,
+            ModerationConfig::default(),
+            connection,
+            None,
+        )
+    }
+
+    #[test]
+    fn reconnects_when_asked_or_dropped() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = IrcConfig {
+            server: "127.0.0.1".to_owned(),
+            port: listener.local_addr().unwrap().port(),
+            channels: vec!["streamer".to_owned()],
+            ..IrcConfig::default()
+        };
+        let connection = ConnectionConfig {
+            backoff_initial_ms: 10,
+            backoff_max_ms: 40,
+            ..ConnectionConfig::default()
+        };
+        let input = input(config, connection);
+        let rx_state = input.connection_states().unwrap();
+        let rx_input = input.create_receiver();
+
+        let (first, _) = serve(
+            &listener,
+            &[(
+                "USER",
+                &[
+                    ":tmi.twitch.tv 001 viewer :Welcome, GLHF!",
+                    ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :a",
+                    ":tmi.twitch.tv RECONNECT",
+                ],
+            )],
+            "USER",
+        );
+        assert_eq!(rx_input.recv().unwrap().0, Command::Action(Button::A));
+
+        let (second, _) = serve(
+            &listener,
+            &[(
+                "USER",
+                &[":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :b"],
+            )],
+            "USER",
+        );
+        assert_eq!(rx_input.recv().unwrap().0, Command::Action(Button::B));
+        drop(second);
+
+        let (third, _) = serve(&listener, &[], "USER");
+        let states: Vec<ConnectionState> = rx_state.iter().take(7).collect();
+        assert_eq!(
+            states,
+            vec![
+                ConnectionState::Connecting,
+                ConnectionState::Connected,
+                ConnectionState::Disconnected {
+                    reason: "the server asked to reconnect".to_owned(),
+                    retry_in_ms: 10,
+                },
+                ConnectionState::Connecting,
+                ConnectionState::Connected,
+                ConnectionState::Disconnected {
+                    reason: "the server closed the connection".to_owned(),
+                    retry_in_ms: 20,
+                },
+                ConnectionState::Connecting,
+            ]
+        );
+
+        drop((first, third));
+    }
+
+    #[test]
+    fn logs_in_with_sasl_and_joins_channels() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = IrcConfig {
+            server: "127.0.0.1".to_owned(),
+            port: listener.local_addr().unwrap().port(),
+            nickname: "bot".to_owned(),
+            password: Some("hunter2".to_owned()),
+            sasl: true,
+            channels: vec!["#tpp".to_owned(), "speedruns".to_owned()],
+            ..IrcConfig::default()
+        };
+        let input = input(config, ConnectionConfig::default());
+        let rx_input = input.create_receiver();
+
+        let (stream, received) = serve(
+            &listener,
+            &[
+                ("CAP REQ :sasl", &[":irc.local CAP * ACK :sasl"]),
+                ("AUTHENTICATE PLAIN", &["AUTHENTICATE +"]),
+                (
+                    "AUTHENTICATE Ym90",
+                    &[
+                        ":irc.local 903 bot :SASL authentication successful",
+                        ":irc.local 001 bot :Welcome",
+                        ":irc.local 422 bot :MOTD File is missing",
+                    ],
+                ),
+                (
+                    "JOIN #speedruns",
+                    &[":viewer!viewer@irc.local PRIVMSG #speedruns :up"],
+                ),
+            ],
+            "JOIN #speedruns",
+        );
+
+        assert_eq!(rx_input.recv().unwrap().0, Command::Action(Button::Up));
+        assert_eq!(
+            received,
+            vec![
+                "CAP REQ :account-tag",
+                "CAP REQ :sasl",
+                "NICK :bot",
+                "USER bot 0 * :bot",
+                "AUTHENTICATE PLAIN",
+                // "bot\0bot\0hunter2"
+                "AUTHENTICATE Ym90AGJvdABodW50ZXIy",
+                "CAP END",
+                "JOIN #tpp",
+                "JOIN #speedruns",
+            ]
+        );
+        assert!(!received.iter().any(|line| line.starts_with("PASS")));
+
+        drop(stream);
+    }
+
+    #[test]
+    fn disconnects_when_sasl_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = IrcConfig {
+            server: "127.0.0.1".to_owned(),
+            port: listener.local_addr().unwrap().port(),
+            nickname: "bot".to_owned(),
+            password: Some("wrong".to_owned()),
+            sasl: true,
+            ..IrcConfig::default()
+        };
+        let input = input(config, ConnectionConfig::default());
+        let rx_state = input.connection_states().unwrap();
+        let _rx_input = input.create_receiver();
+
+        let (stream, received) = serve(
+            &listener,
+            &[
+                ("CAP REQ :sasl", &[":irc.local CAP * ACK :sasl"]),
+                ("AUTHENTICATE PLAIN", &["AUTHENTICATE +"]),
+                (
+                    "AUTHENTICATE Ym90",
+                    &[":irc.local 904 bot :SASL authentication failed"],
+                ),
+            ],
+            "AUTHENTICATE Ym90",
+        );
+
+        let states: Vec<ConnectionState> = rx_state.iter().take(3).collect();
+        assert_eq!(
+            states[2],
+            ConnectionState::Disconnected {
+                reason: "couldn't log in, the server refused the SASL login".to_owned(),
+                retry_in_ms: 1000,
+            }
+        );
+        assert!(!received.iter().any(|line| line.starts_with("CAP END")));
+
+        drop(stream);
+    }
+
+    #[test]
+    fn takes_operators_by_their_account() {
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let (tx_control, rx_control) = channel();
+        let (tx_input, _rx_input) = channel();
+        let handler = MessageHandler {
+            twitch: false,
+            source: None,
+            chat: ChatHandler {
+                name: "irc_input",
+                registry,
+                tx_control,
+                moderation: ModerationConfig {
+                    operators: vec!["someone".to_owned()],
+                    ..ModerationConfig::default()
+                },
+                tx_input,
+                recorder: None,
+            },
+        };
+        let message = |line: &str| line.parse::<Message>().unwrap();
+
+        // Only the account counts, not the nick.
+        handler.handle(message(":someone!u@host PRIVMSG #tpp :!pause"), None);
+        handler.handle(
+            message("@account=* :someone!u@host PRIVMSG #tpp :!pause"),
+            None,
+        );
+        assert!(rx_control.try_recv().is_err());
+
+        handler.handle(
+            message("@account=Someone :nick!u@host PRIVMSG #tpp :!pause"),
+            None,
+        );
+        assert_eq!(rx_control.try_recv().unwrap(), MediatorControl::Pause(None));
+    }
+
+    #[test]
+    fn reads_roles_from_channel_status() {
+        let users = vec![
+            User::new("~streamer"),
+            User::new("@op"),
+            User::new("+voiced"),
+        ];
+
+        assert_eq!(
+            IrcInput::channel_role(&users, "streamer"),
+            Role::Broadcaster
+        );
+        assert_eq!(IrcInput::channel_role(&users, "OP"), Role::Moderator);
+        assert_eq!(IrcInput::channel_role(&users, "voiced"), Role::Viewer);
+        assert_eq!(IrcInput::channel_role(&users, "stranger"), Role::Viewer);
+    }
+
+    #[test]
+    fn follows_twitch_timeouts_and_bans() {
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let (tx_control, rx_control) = channel();
+        let (tx_input, _rx_input) = channel();
+        let handler = MessageHandler {
+            twitch: true,
+            source: Some("twitch".to_owned()),
+            chat: ChatHandler {
+                name: "twitch_input",
+                registry,
+                tx_control,
+                moderation: ModerationConfig::default(),
+                tx_input,
+                recorder: None,
+            },
+        };
+        let message = |line: &str| line.parse::<Message>().unwrap();
+
+        handler.handle(
+            message("@ban-duration=600 :tmi.twitch.tv CLEARCHAT #streamer :Troll"),
+            None,
+        );
+        handler.handle(message(":tmi.twitch.tv CLEARCHAT #streamer :spammer"), None);
+        handler.handle(message(":tmi.twitch.tv CLEARCHAT #streamer"), None);
+
+        assert_eq!(
+            rx_control.try_iter().collect::<Vec<_>>(),
+            vec![
+                MediatorControl::ChatBan("twitch:troll".to_owned(), Some(600)),
+                MediatorControl::ChatBan("twitch:spammer".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_chatter_from_tags() {
+        let tag = |key: &str, value: &str| Tag(key.to_owned(), Some(value.to_owned()));
+        let tags = vec![
+            tag("badges", "vip/1,subscriber/12"),
+            tag("color", ""),
+            tag("display-name", "SomeOne"),
+            tag("id", "b34ccfc7-4977-403a-8a94-33c6bac34fb8"),
+            tag("mod", "0"),
+            tag("tmi-sent-ts", "1507246572675"),
+            tag("user-id", "1337"),
+        ];
+
+        let chatter = IrcInput::chatter("someone", &tags);
+
+        assert_eq!(chatter.name, "someone");
+        assert_eq!(chatter.display_name.as_deref(), Some("SomeOne"));
+        assert_eq!(chatter.id.as_deref(), Some("1337"));
+        assert_eq!(chatter.color, None);
+        assert_eq!(chatter.badges, vec!["vip", "subscriber"]);
+        assert!(chatter.vip && chatter.subscriber && !chatter.moderator);
+        assert_eq!(chatter.sent_at, Some(1507246572675));
+        assert_eq!(IrcInput::chatter("someone", &[]), Chatter::new("someone"));
+    }
+}
@@ -0,0 +1,226 @@
+use super::{ChatHandler, Chatter, CommandInput, Input, Recorder};
+use crate::mediator::MediatorControlSender;
+use crate::moderation::{ModerationConfig, Role};
+use crate::vote_system::VoteSystemRegistry;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+// Longer lines than chats allow drop the connection, so nothing reads forever.
+const MAX_LINE_BYTES: usize = 512;
+// Each connection gets a thread, so only this many are taken at once.
+const MAX_CONNECTIONS: usize = 64;
+
+// Reads lines like "someone a", each a user and what they sent to chat. Anyone can
+// type any name, so everyone is a viewer.
+fn read_lines<R: BufRead>(mut reader: R, handler: &ChatHandler) {
+    loop {
+        let mut line = String::new();
+        let read = (&mut reader)
+            .take(MAX_LINE_BYTES as u64 + 1)
+            .read_line(&mut line);
+        match read {
+            Ok(0) => break,
+            Ok(n) if n > MAX_LINE_BYTES && !line.ends_with('\n') => {
+                println!(
+                    "{}: a line is longer than {} bytes, stopping.",
+                    handler.name, MAX_LINE_BYTES
+                );
+                break;
+            }
+            Ok(_) => (),
+            Err(e) => {
+                println!("{}: couldn't read, {}.", handler.name, e);
+                break;
+            }
+        }
+
+        let mut parts = line.trim().splitn(2, char::is_whitespace);
+        match (parts.next(), parts.next()) {
+            (Some(user), Some(msg)) if !user.is_empty() => {
+                handler.handle(msg.trim(), Chatter::new(user), Role::Viewer);
+            }
+            _ => println!(
+                "{}: ignoring {:?}, expected a user and a command.",
+                handler.name, line
+            ),
+        }
+    }
+}
+
+// Reads inputs typed into the terminal, for trying things out without a chat.
+pub struct StdinInput {
+    registry: Arc<VoteSystemRegistry>,
+    tx_control: MediatorControlSender,
+    moderation: ModerationConfig,
+    recorder: Option<Recorder>,
+}
+
+impl StdinInput {
+    pub fn new(
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        StdinInput {
+            registry,
+            tx_control,
+            moderation,
+            recorder,
+        }
+    }
+}
+
+impl CommandInput for StdinInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        let (tx, rx) = channel();
+        let handler = ChatHandler {
+            name: "stdin_input",
+            registry: self.registry.clone(),
+            tx_control: self.tx_control.clone(),
+            moderation: self.moderation.clone(),
+            tx_input: tx,
+            recorder: self.recorder.clone(),
+        };
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            read_lines(stdin.lock(), &handler);
+        });
+
+        rx
+    }
+}
+
+// Reads inputs from whoever connects to a local port, like bots and test scripts.
+pub struct TcpInput {
+    listener: TcpListener,
+    registry: Arc<VoteSystemRegistry>,
+    tx_control: MediatorControlSender,
+    moderation: ModerationConfig,
+    recorder: Option<Recorder>,
+}
+
+impl TcpInput {
+    pub fn bind<A: ToSocketAddrs>(
+        address: A,
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        recorder: Option<Recorder>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+
+        Ok(TcpInput {
+            listener,
+            registry,
+            tx_control,
+            moderation,
+            recorder,
+        })
+    }
+}
+
+impl CommandInput for TcpInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        let (tx, rx) = channel();
+        let listener = self.listener.try_clone().unwrap();
+        let handler = ChatHandler {
+            name: "tcp_input",
+            registry: self.registry.clone(),
+            tx_control: self.tx_control.clone(),
+            moderation: self.moderation.clone(),
+            tx_input: tx,
+            recorder: self.recorder.clone(),
+        };
+        let connections = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        println!("tcp_input: couldn't accept a connection, {}.", e);
+                        continue;
+                    }
+                };
+                if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    println!(
+                        "tcp_input: refusing {:?}, already {} connected.",
+                        stream.peer_addr(),
+                        MAX_CONNECTIONS
+                    );
+                    continue;
+                }
+                println!("tcp_input: {:?} connected.", stream.peer_addr());
+
+                connections.fetch_add(1, Ordering::SeqCst);
+                let connections = connections.clone();
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    read_lines(BufReader::new(stream), &handler);
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Button, Command};
+    use crate::mediator::MediatorControl;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    #[test]
+    fn reads_inputs_from_each_connection() {
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let (tx_control, rx_control) = channel();
+        let moderation = ModerationConfig {
+            permissions: vec![("pause".to_owned(), Role::Viewer)]
+                .into_iter()
+                .collect(),
+            ..ModerationConfig::default()
+        };
+        let input = TcpInput::bind("127.0.0.1:0", registry, tx_control, moderation, None).unwrap();
+        let address = input.listener.local_addr().unwrap();
+        let rx_input = input.create_receiver();
+
+        let mut first = TcpStream::connect(address).unwrap();
+        let mut second = TcpStream::connect(address).unwrap();
+        write!(first, "someone a\n\nnobody\n").unwrap();
+        assert_eq!(
+            rx_input.recv().unwrap(),
+            Input(Command::Action(Button::A), Chatter::new("someone"))
+        );
+
+        // Everyone is a viewer, so only what viewers may do goes through.
+        write!(
+            second,
+            "someone_else  b \r\nsomeone_else !pause\nsomeone_else !resume\n"
+        )
+        .unwrap();
+        assert_eq!(
+            rx_input.recv().unwrap(),
+            Input(Command::Action(Button::B), Chatter::new("someone_else"))
+        );
+        assert_eq!(rx_control.recv().unwrap(), MediatorControl::Pause(None));
+
+        let long_line = format!("someone {}\nsomeone b\n", "a".repeat(MAX_LINE_BYTES));
+        write!(first, "{}", long_line).unwrap();
+        assert!(matches!(first.read(&mut [0; 1]), Ok(0) | Err(_)));
+        writeln!(second, "someone_else up").unwrap();
+        assert_eq!(
+            rx_input.recv().unwrap(),
+            Input(Command::Action(Button::Up), Chatter::new("someone_else"))
+        );
+        assert!(rx_control.try_recv().is_err());
+        assert!(rx_input.try_recv().is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Chatter, CommandInput, Input};
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+use crate::vote_system::{VoteSystem, VoteSystemRegistry};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+// How often it wakes up to send what's due.
+const TICK: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoadConfig {
+    // How many made up chatters send the messages, named load_0, load_1 and so on.
+    pub chatters: usize,
+    // Between all of them.
+    pub messages_per_sec: f64,
+    // How often each button is pressed, compared to the others. All are as likely without any.
+    pub buttons: HashMap<String, f64>,
+    // The share of messages voting for a vote system, instead of pressing a button.
+    pub mode_vote_chance: f64,
+    // Like buttons, by the keyword chatters vote with. All are as likely without any.
+    pub modes: HashMap<String, f64>,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        LoadConfig {
+            chatters: 1000,
+            messages_per_sec: 1000.0,
+            buttons: HashMap::new(),
+            mode_vote_chance: 0.01,
+            modes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Choices<T> {
+    items: Vec<T>,
+    weights: WeightedIndex<f64>,
+}
+
+impl<T> Choices<T> {
+    fn new(choices: Vec<(T, f64)>) -> Result<Self, String> {
+        let weights = WeightedIndex::new(choices.iter().map(|(_, weight)| *weight))
+            .map_err(|e| format!("bad weights, {:?}", e))?;
+        let items = choices.into_iter().map(|(item, _)| item).collect();
+
+        Ok(Choices { items, weights })
+    }
+
+    fn pick<R: Rng>(&self, rng: &mut R) -> &T {
+        &self.items[self.weights.sample(rng)]
+    }
+}
+
+// Makes up chat for soak testing everything after the input, timing inputs so control can
+// tell how long they took to get there.
+#[derive(Clone)]
+pub struct LoadInput {
+    chatters: usize,
+    messages_per_sec: f64,
+    buttons: Choices<Button>,
+    mode_vote_chance: f64,
+    modes: Choices<VoteSystem>,
+    clock: SharedClock,
+}
+
+impl LoadInput {
+    pub fn new(
+        config: LoadConfig,
+        registry: &VoteSystemRegistry,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        if config.chatters == 0 {
+            return Err("load_input: needs at least one chatter".to_owned());
+        }
+        if !(0.0..=1.0).contains(&config.mode_vote_chance) {
+            return Err("load_input: mode_vote_chance should be between 0 and 1".to_owned());
+        }
+
+        let buttons = if config.buttons.is_empty() {
+            Button::ALL.iter().map(|button| (*button, 1.0)).collect()
+        } else {
+            config
+                .buttons
+                .into_iter()
+                .map(|(name, weight)| match Button::from_string(name.clone()) {
+                    Some(button) => Ok((button, weight)),
+                    None => Err(format!("load_input: unknown button {}", name)),
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let modes = if config.modes.is_empty() {
+            registry
+                .systems()
+                .into_iter()
+                .map(|system| (system, 1.0))
+                .collect()
+        } else {
+            config
+                .modes
+                .into_iter()
+                .map(|(keyword, weight)| match registry.by_keyword(&keyword) {
+                    Some(system) => Ok((system, weight)),
+                    None => Err(format!(
+                        "load_input: unknown vote system keyword {}",
+                        keyword
+                    )),
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(LoadInput {
+            chatters: config.chatters,
+            messages_per_sec: config.messages_per_sec,
+            buttons: Choices::new(buttons)
+                .map_err(|e| format!("load_input: buttons have {}", e))?,
+            mode_vote_chance: config.mode_vote_chance,
+            modes: Choices::new(modes).map_err(|e| format!("load_input: modes have {}", e))?,
+            clock,
+        })
+    }
+
+    fn next_input<R: Rng>(&self, rng: &mut R) -> Input {
+        let command = if rng.gen_bool(self.mode_vote_chance) {
+            Command::ChangeVoteSystem(self.modes.pick(rng).clone())
+        } else {
+            Command::Action(*self.buttons.pick(rng))
+        };
+        let name = format!("load_{}", rng.gen_range(0, self.chatters));
+        let chatter = Chatter {
+            sent_at: Some(self.clock.unix_now_ms()),
+            ..Chatter::new(&name)
+        };
+
+        Input(command, chatter)
+    }
+
+    fn run(&self, tx: &Sender<Input>) {
+        let mut rng = rand::thread_rng();
+        let start = self.clock.now();
+        let mut sent = 0;
+
+        loop {
+            // Catches up on everything due since the start, so a late tick doesn't slow it down.
+            let due = (self.clock.elapsed(start).as_secs_f64() * self.messages_per_sec) as u64;
+            while sent < due {
+                if tx.send(self.next_input(&mut rng)).is_err() {
+                    return;
+                }
+                sent += 1;
+            }
+
+            self.clock.sleep(TICK);
+        }
+    }
+}
+
+impl CommandInput for LoadInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        let (tx, rx) = channel();
+        let load = self.clone();
+
+        println!(
+            "load_input: sending {} messages/s from {} chatters.",
+            self.messages_per_sec, self.chatters
+        );
+        // Inputs skip the chat handler, so the logs don't slow it down.
+        thread::spawn(move || load.run(&tx));
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+
+    #[test]
+    fn sends_inputs_at_the_configured_rate() {
+        let clock = Arc::new(ManualClock::new());
+        let registry = VoteSystemRegistry::with_builtin_systems(&Default::default());
+        let config = LoadConfig {
+            chatters: 3,
+            messages_per_sec: 100.0,
+            buttons: vec![("a".to_owned(), 1.0)].into_iter().collect(),
+            mode_vote_chance: 0.5,
+            modes: vec![("democracy".to_owned(), 1.0)].into_iter().collect(),
+        };
+        let input = LoadInput::new(config.clone(), &registry, clock.clone()).unwrap();
+        let rx_input = input.create_receiver();
+
+        clock.wait_for_sleepers(1);
+        clock.advance(Duration::from_secs(1));
+        let inputs: Vec<Input> = rx_input.iter().take(100).collect();
+        clock.wait_for_sleepers(1);
+        assert!(rx_input.try_recv().is_err());
+
+        let democracy = Command::ChangeVoteSystem(VoteSystem::new("Democracy"));
+        assert!(inputs.iter().any(|Input(command, _)| *command == democracy));
+        assert!(inputs
+            .iter()
+            .any(|Input(command, _)| *command == Command::Action(Button::A)));
+        for Input(_, chatter) in &inputs {
+            assert!(["load_0", "load_1", "load_2"].contains(&chatter.name.as_str()));
+            assert_eq!(chatter.sent_at, Some(1000));
+        }
+
+        let config = LoadConfig {
+            buttons: vec![("jump".to_owned(), 1.0)].into_iter().collect(),
+            ..config
+        };
+        assert!(LoadInput::new(config, &registry, clock).is_err());
+    }
+}
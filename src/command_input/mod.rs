@@ -1,14 +1,143 @@
 use super::command::Command;
+use crate::logging::logs_inputs;
+use crate::mediator::{MediatorControl, MediatorControlSender};
+use crate::moderation::{ModerationConfig, Role};
+use crate::vote_system::VoteSystemRegistry;
 use serde::Serialize;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
 
-type User = String;
-#[derive(Debug, Serialize, Clone)]
-pub struct Input(pub Command, pub User);
+// A chatter's login name.
+pub type User = String;
+
+// Who sent an input, with whatever the chat service told about them.
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct Chatter {
+    pub name: User,
+    pub id: Option<String>,
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    pub badges: Vec<String>,
+    pub moderator: bool,
+    pub subscriber: bool,
+    pub vip: bool,
+    // The account the server vouches for. On Twitch it's the login, elsewhere the
+    // services account, if the server tells.
+    pub account: Option<String>,
+    pub message_id: Option<String>,
+    // Unix timestamp in milliseconds, from the server.
+    pub sent_at: Option<u64>,
+    // Which of the sources it came from, when reading from several.
+    pub source: Option<String>,
+}
+
+impl Chatter {
+    pub fn new(name: &str) -> Self {
+        Chatter {
+            name: name.to_owned(),
+            ..Chatter::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Input(pub Command, pub Chatter);
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    // Lost the connection, or couldn't make one, and will retry after a while.
+    Disconnected { reason: String, retry_in_ms: u64 },
+}
 
 pub trait CommandInput: Send {
     fn create_receiver(&self) -> Receiver<Input>;
+
+    // Inputs that connect somewhere report how that's going. Can only be taken once.
+    fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+        None
+    }
+}
+
+// Turns chat messages into inputs and control commands, wherever they come from.
+#[derive(Clone)]
+struct ChatHandler {
+    // Which input is handling them, for the logs.
+    name: &'static str,
+    registry: Arc<VoteSystemRegistry>,
+    tx_control: MediatorControlSender,
+    moderation: ModerationConfig,
+    tx_input: Sender<Input>,
+    recorder: Option<Recorder>,
 }
 
+impl ChatHandler {
+    // Whether the message was a command, or a control.
+    fn handle(&self, msg: &str, chatter: Chatter, role: Role) -> bool {
+        if let Some(ref recorder) = self.recorder {
+            recorder.record(&chatter.name, msg);
+        }
+
+        // Control commands are never gameplay, even when they're not allowed.
+        if let Some(control) = MediatorControl::from_string(msg, &self.registry) {
+            if self.moderation.allows(role, &control) {
+                println!(
+                    "{}: got {:?} control, from {}.",
+                    self.name, control, chatter.name
+                );
+                self.tx_control.send(control).unwrap();
+            } else {
+                println!("{}: {} can't send {:?}.", self.name, chatter.name, control);
+            }
+
+            true
+        } else if let Some(command) = Command::from_string(msg.to_owned(), &self.registry) {
+            if logs_inputs() {
+                println!(
+                    "{}: got {:?} command, from {}.",
+                    self.name, command, chatter.name
+                );
+            }
+
+            self.tx_input.send(Input(command, chatter)).unwrap();
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Lets the input be picked from the settings.
+impl CommandInput for Box<dyn CommandInput> {
+    fn create_receiver(&self) -> Receiver<Input> {
+        (**self).create_receiver()
+    }
+
+    fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+        (**self).connection_states()
+    }
+}
+
+pub mod http_input;
+pub use http_input::{tokens_match, HttpInput, HttpInputSender, InputMessage};
+
+pub mod irc_input;
+pub use irc_input::{ConnectionConfig, IrcConfig, IrcInput};
+
+pub mod line_input;
+pub use line_input::{StdinInput, TcpInput};
+
+pub mod load_input;
+pub use load_input::{LoadConfig, LoadInput};
+
+pub mod multi_input;
+pub use multi_input::{MultiInput, SourceConfig, SourceKind};
+
+pub mod replay_input;
+pub use replay_input::{Recorder, RecorderConfig, ReplayInput};
+
 pub mod twitch_input;
-pub use twitch_input::TwitchInput;
+pub use twitch_input::{Login, TwitchInput};
@@ -0,0 +1,293 @@
+use super::{Chatter, CommandInput, ConnectionState, Input, IrcConfig, LoadConfig};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SourceKind {
+    // Logs in as set up at the top of the settings. Without channels, reads the default ones.
+    Twitch {
+        #[serde(default)]
+        channels: Vec<String>,
+    },
+    Irc(IrcConfig),
+    // Reads lines like "someone a" from the terminal.
+    Stdin,
+    // Reads lines like "someone a" from whoever connects to the address.
+    Tcp {
+        address: String,
+    },
+    // Takes inputs posted to the HTTP server with the token and, if given an address,
+    // from WebSockets connecting there.
+    Http {
+        token: String,
+        #[serde(default)]
+        websocket_address: Option<String>,
+    },
+    // Plays back a recorded session log.
+    Replay {
+        path: String,
+        // How many times faster than real time. Zero replays as fast as it can.
+        #[serde(default = "real_time")]
+        speed: f64,
+    },
+    // Makes up chatters pressing buttons and voting, for soak testing.
+    Load(LoadConfig),
+}
+
+fn real_time() -> f64 {
+    1.0
+}
+
+// Slower than this, a replay would take ages, and dividing by the speed could overflow.
+const MIN_REPLAY_SPEED: f64 = 0.01;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SourceConfig {
+    // Inputs from the source are tagged with it.
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SourceKind,
+}
+
+impl SourceConfig {
+    // Names tell the sources apart, in bans like "irc:someone" too.
+    pub fn validate_all(sources: &[SourceConfig]) -> Result<(), String> {
+        let mut seen = HashSet::new();
+
+        for source in sources {
+            if source.name.is_empty() || source.name.contains(':') {
+                return Err(format!(
+                    "inputs: {:?} isn't a name, it can't be empty or have a ':'",
+                    source.name
+                ));
+            }
+            if !seen.insert(source.name.as_str()) {
+                return Err(format!("inputs: more than one is called {}", source.name));
+            }
+            if let SourceKind::Replay { speed, .. } = source.kind {
+                if speed != 0.0 && !(speed.is_finite() && speed >= MIN_REPLAY_SPEED) {
+                    return Err(format!(
+                        "inputs: {} has a speed of {}, it must be 0 or at least {}",
+                        source.name, speed, MIN_REPLAY_SPEED
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Reads from several inputs at once, tagging each input with the source it came from.
+pub struct MultiInput {
+    sources: Vec<(String, Box<dyn CommandInput>)>,
+}
+
+impl MultiInput {
+    pub fn new(sources: Vec<(String, Box<dyn CommandInput>)>) -> Self {
+        MultiInput { sources }
+    }
+
+    // The worst of the sources' states, so it shows when any of them is down.
+    fn merged_state(states: &[(String, Option<ConnectionState>)]) -> ConnectionState {
+        let disconnected = states.iter().find_map(|(name, state)| match state {
+            Some(ConnectionState::Disconnected {
+                reason,
+                retry_in_ms,
+            }) => Some(ConnectionState::Disconnected {
+                reason: format!("{}: {}", name, reason),
+                retry_in_ms: *retry_in_ms,
+            }),
+            _ => None,
+        });
+
+        if let Some(disconnected) = disconnected {
+            disconnected
+        } else if states
+            .iter()
+            .all(|(_, state)| *state == Some(ConnectionState::Connected))
+        {
+            ConnectionState::Connected
+        } else {
+            ConnectionState::Connecting
+        }
+    }
+}
+
+impl CommandInput for MultiInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        let (tx, rx) = channel();
+
+        for (name, input) in &self.sources {
+            let rx_source = input.create_receiver();
+            let tx = tx.clone();
+            let name = name.clone();
+            thread::spawn(move || {
+                for Input(command, chatter) in rx_source.iter() {
+                    let chatter = Chatter {
+                        source: Some(name.clone()),
+                        ..chatter
+                    };
+
+                    if tx.send(Input(command, chatter)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        rx
+    }
+
+    fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+        let (tx_source_state, rx_source_state) = channel();
+        let mut states = Vec::new();
+
+        for (name, input) in &self.sources {
+            if let Some(rx_state) = input.connection_states() {
+                let index = states.len();
+                states.push((name.clone(), None));
+
+                let tx_source_state = tx_source_state.clone();
+                thread::spawn(move || {
+                    for state in rx_state.iter() {
+                        if tx_source_state.send((index, state)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        }
+
+        if states.is_empty() {
+            return None;
+        }
+
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            let mut last_state = None;
+
+            for (index, state) in rx_source_state.iter() {
+                states[index].1 = Some(state);
+
+                let state = Self::merged_state(&states);
+                if last_state.as_ref() != Some(&state) {
+                    last_state = Some(state.clone());
+
+                    if tx.send(state).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Some(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Button, Command};
+    use std::sync::mpsc::Sender;
+    use std::sync::Mutex;
+
+    struct FakeInput {
+        rx_input: Mutex<Option<Receiver<Input>>>,
+        rx_state: Mutex<Option<Receiver<ConnectionState>>>,
+    }
+
+    impl FakeInput {
+        fn new() -> (Self, Sender<Input>, Sender<ConnectionState>) {
+            let (tx_input, rx_input) = channel();
+            let (tx_state, rx_state) = channel();
+            let input = FakeInput {
+                rx_input: Mutex::new(Some(rx_input)),
+                rx_state: Mutex::new(Some(rx_state)),
+            };
+
+            (input, tx_input, tx_state)
+        }
+    }
+
+    impl CommandInput for FakeInput {
+        fn create_receiver(&self) -> Receiver<Input> {
+            self.rx_input.lock().unwrap().take().unwrap()
+        }
+
+        fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+            self.rx_state.lock().unwrap().take()
+        }
+    }
+
+    #[test]
+    fn tags_inputs_and_merges_connection_states() {
+        let (twitch, tx_twitch, tx_twitch_state) = FakeInput::new();
+        let (irc, tx_irc, tx_irc_state) = FakeInput::new();
+        let input = MultiInput::new(vec![
+            ("twitch".to_owned(), Box::new(twitch)),
+            ("irc".to_owned(), Box::new(irc)),
+        ]);
+        let rx_state = input.connection_states().unwrap();
+        let rx_input = input.create_receiver();
+
+        tx_irc
+            .send(Input(Command::Action(Button::A), Chatter::new("someone")))
+            .unwrap();
+        let Input(command, chatter) = rx_input.recv().unwrap();
+        assert_eq!(command, Command::Action(Button::A));
+        assert_eq!(chatter.source.as_deref(), Some("irc"));
+
+        tx_twitch
+            .send(Input(Command::Action(Button::B), Chatter::new("someone")))
+            .unwrap();
+        assert_eq!(rx_input.recv().unwrap().1.source.as_deref(), Some("twitch"));
+
+        tx_twitch_state.send(ConnectionState::Connected).unwrap();
+        assert_eq!(rx_state.recv().unwrap(), ConnectionState::Connecting);
+        tx_irc_state
+            .send(ConnectionState::Disconnected {
+                reason: "timed out".to_owned(),
+                retry_in_ms: 1000,
+            })
+            .unwrap();
+        assert_eq!(
+            rx_state.recv().unwrap(),
+            ConnectionState::Disconnected {
+                reason: "irc: timed out".to_owned(),
+                retry_in_ms: 1000,
+            }
+        );
+        tx_irc_state.send(ConnectionState::Connected).unwrap();
+        assert_eq!(rx_state.recv().unwrap(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn refuses_sources_with_the_same_name_or_a_bad_speed() {
+        let source = |name: &str| SourceConfig {
+            name: name.to_owned(),
+            kind: SourceKind::Stdin,
+        };
+
+        assert!(SourceConfig::validate_all(&[source("terminal"), source("irc")]).is_ok());
+        assert!(SourceConfig::validate_all(&[source("irc"), source("irc")]).is_err());
+        assert!(SourceConfig::validate_all(&[source("irc:2")]).is_err());
+
+        let replay = |speed: f64| SourceConfig {
+            name: "replay".to_owned(),
+            kind: SourceKind::Replay {
+                path: "session.jsonl".to_owned(),
+                speed,
+            },
+        };
+        assert!(SourceConfig::validate_all(&[replay(0.0)]).is_ok());
+        assert!(SourceConfig::validate_all(&[replay(0.5)]).is_ok());
+        assert!(SourceConfig::validate_all(&[replay(0.001)]).is_err());
+        assert!(SourceConfig::validate_all(&[replay(-1.0)]).is_err());
+        assert!(SourceConfig::validate_all(&[replay(f64::NAN)]).is_err());
+        assert!(SourceConfig::validate_all(&[replay(f64::INFINITY)]).is_err());
+    }
+}
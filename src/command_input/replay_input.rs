@@ -0,0 +1,287 @@
+use super::{ChatHandler, Chatter, CommandInput, Input, SourceConfig, SourceKind};
+use crate::clock::SharedClock;
+use crate::mediator::{MediatorControl, MediatorControlSender};
+use crate::moderation::{ModerationConfig, Role};
+use crate::vote_system::VoteSystemRegistry;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RecorderConfig {
+    // Where to write the session log. Nothing is recorded without it.
+    pub path: Option<String>,
+}
+
+impl RecorderConfig {
+    // Recording into the log being replayed would read back what it just wrote.
+    pub fn validate(&self, sources: &[SourceConfig]) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        let same_file = |other: &str| {
+            other == path
+                || match (fs::canonicalize(path), fs::canonicalize(other)) {
+                    (Ok(a), Ok(b)) => a == b,
+                    _ => false,
+                }
+        };
+
+        match sources.iter().find(|source| match source.kind {
+            SourceKind::Replay {
+                path: ref other, ..
+            } => same_file(other),
+            _ => false,
+        }) {
+            Some(source) => Err(format!(
+                "recorder: {} is replayed by {}, record somewhere else",
+                path, source.name
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+// A line of the session log, in JSON.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LogEntry {
+    // Since the recording started.
+    pub at_ms: u64,
+    pub user: String,
+    pub message: String,
+}
+
+// Writes every chat message the inputs get to a session log, after any earlier sessions.
+#[derive(Clone)]
+pub struct Recorder {
+    writer: Arc<Mutex<LineWriter<File>>>,
+    clock: SharedClock,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &str, clock: SharedClock) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let writer = LineWriter::new(file);
+        let start = clock.now();
+
+        Ok(Recorder {
+            writer: Arc::new(Mutex::new(writer)),
+            clock,
+            start,
+        })
+    }
+
+    pub fn record(&self, user: &str, message: &str) {
+        let entry = LogEntry {
+            at_ms: self.clock.elapsed(self.start).as_millis() as u64,
+            user: user.to_owned(),
+            message: message.to_owned(),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+
+        if let Err(e) = writeln!(self.writer.lock().unwrap(), "{}", line) {
+            println!("recorder: couldn't write, {}.", e);
+        }
+    }
+}
+
+// Plays a session log back, keeping the time between messages. Who could do what isn't
+// known anymore, so controls are skipped and everyone is a viewer.
+pub struct ReplayInput {
+    path: String,
+    // How many times faster than real time. Zero replays as fast as it can.
+    speed: f64,
+    registry: Arc<VoteSystemRegistry>,
+    tx_control: MediatorControlSender,
+    moderation: ModerationConfig,
+    clock: SharedClock,
+}
+
+impl ReplayInput {
+    pub fn new(
+        path: String,
+        speed: f64,
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        clock: SharedClock,
+    ) -> Self {
+        ReplayInput {
+            path,
+            speed,
+            registry,
+            tx_control,
+            moderation,
+            clock,
+        }
+    }
+
+    fn replay(
+        path: &str,
+        speed: f64,
+        handler: &ChatHandler,
+        clock: &SharedClock,
+        start: Instant,
+    ) -> io::Result<()> {
+        let reader = BufReader::new(File::open(path)?);
+        // Each session in the log starts over from zero, and plays after the one before.
+        let mut offset_ms = 0;
+        let mut last_ms = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            let entry: LogEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    println!("replay_input: skipping {:?}, {}.", line, e);
+                    continue;
+                }
+            };
+            if entry.at_ms < last_ms {
+                offset_ms += last_ms;
+            }
+            last_ms = entry.at_ms;
+
+            if MediatorControl::from_string(&entry.message, &handler.registry).is_some() {
+                continue;
+            }
+
+            // Deadlines are from the start, so time spent handling doesn't add up.
+            if speed > 0.0 {
+                let deadline =
+                    start + Duration::from_millis(offset_ms + entry.at_ms).div_f64(speed);
+                let now = clock.now();
+                if deadline > now {
+                    clock.sleep(deadline - now);
+                }
+            }
+
+            handler.handle(&entry.message, Chatter::new(&entry.user), Role::Viewer);
+        }
+
+        Ok(())
+    }
+}
+
+impl CommandInput for ReplayInput {
+    fn create_receiver(&self) -> Receiver<Input> {
+        let (tx, rx) = channel();
+        let path = self.path.clone();
+        let speed = self.speed;
+        let clock = self.clock.clone();
+        let start = clock.now();
+        let handler = ChatHandler {
+            name: "replay_input",
+            registry: self.registry.clone(),
+            tx_control: self.tx_control.clone(),
+            moderation: self.moderation.clone(),
+            tx_input: tx,
+            // Replaying into the log it's reading from would be no good.
+            recorder: None,
+        };
+        thread::spawn(
+            move || match ReplayInput::replay(&path, speed, &handler, &clock, start) {
+                Ok(()) => println!("replay_input: done replaying {}.", path),
+                Err(e) => println!("replay_input: couldn't replay {}, {}.", path, e),
+            },
+        );
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::command::{Button, Command};
+    use std::env;
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let manual_clock = Arc::new(ManualClock::new());
+        let clock: SharedClock = manual_clock.clone();
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+        let path = env::temp_dir().join(format!("tpp_rs_replay_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap().to_owned();
+        // Recording appends, so nothing from an earlier run may be left.
+        let _ = fs::remove_file(&path);
+
+        let (tx_input, _rx_input) = channel();
+        let (tx_control, _rx_control) = channel();
+        let mut recording = ChatHandler {
+            name: "test",
+            registry: registry.clone(),
+            tx_control,
+            moderation: ModerationConfig::default(),
+            tx_input,
+            recorder: Some(Recorder::create(&path, clock.clone()).unwrap()),
+        };
+        recording.handle("a", Chatter::new("someone"), Role::Viewer);
+        manual_clock.advance(Duration::from_millis(1500));
+        recording.handle("hello", Chatter::new("someone"), Role::Viewer);
+        recording.handle("!pause", Chatter::new("mod"), Role::Moderator);
+        recording.handle("b", Chatter::new("someone"), Role::Viewer);
+
+        // A later session goes after the first one.
+        recording.recorder = Some(Recorder::create(&path, clock.clone()).unwrap());
+        recording.handle("up", Chatter::new("someone"), Role::Viewer);
+        manual_clock.advance(Duration::from_millis(500));
+        recording.handle("down", Chatter::new("someone"), Role::Viewer);
+        drop(recording);
+
+        let entries: Vec<LogEntry> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            entries.iter().map(|e| e.at_ms).collect::<Vec<_>>(),
+            vec![0, 1500, 1500, 1500, 0, 500]
+        );
+
+        let replayed = SourceConfig {
+            name: "replay".to_owned(),
+            kind: SourceKind::Replay {
+                path: path.clone(),
+                speed: 2.0,
+            },
+        };
+        let recorder = RecorderConfig {
+            path: Some(path.clone()),
+        };
+        assert!(recorder.validate(&[replayed]).is_err());
+
+        let (tx_control, rx_control) = channel();
+        let replay = ReplayInput::new(
+            path.clone(),
+            2.0,
+            registry,
+            tx_control,
+            ModerationConfig::default(),
+            clock,
+        );
+        let rx_input = replay.create_receiver();
+        let pressed = |button: Button| Input(Command::Action(button), Chatter::new("someone"));
+
+        assert_eq!(rx_input.recv().unwrap(), pressed(Button::A));
+        manual_clock.wait_for_sleepers(1);
+        manual_clock.advance(Duration::from_millis(750));
+        assert_eq!(rx_input.recv().unwrap(), pressed(Button::B));
+        assert_eq!(rx_input.recv().unwrap(), pressed(Button::Up));
+        manual_clock.wait_for_sleepers(1);
+        manual_clock.advance(Duration::from_millis(250));
+        assert_eq!(rx_input.recv().unwrap(), pressed(Button::Down));
+        // Controls aren't replayed, whoever sent them.
+        assert!(rx_control.try_recv().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
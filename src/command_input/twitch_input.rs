@@ -1,63 +1,84 @@
-use super::{CommandInput, Input};
-use crate::command::{Button, Command};
-use irc::client::prelude::IrcClient;
-use irc::error::IrcError;
+use super::{CommandInput, ConnectionConfig, ConnectionState, Input, IrcInput, Recorder};
+use crate::mediator::MediatorControlSender;
+use crate::moderation::ModerationConfig;
+use crate::vote_system::VoteSystemRegistry;
+use irc::client::prelude::Config;
+use rand::Rng;
 use std::default::Default;
-use std::sync::mpsc::{channel, Receiver};
-use std::thread;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+pub enum Login {
+    // Reads chat without an account, which can't send anything back.
+    Anonymous,
+    Account {
+        username: String,
+        oauth_token: String,
+    },
+}
 
 pub struct TwitchInput {
-    client: IrcClient,
+    input: IrcInput,
 }
 
 impl TwitchInput {
-    pub fn new(username: String, oauth_token: String) -> Result<Self, IrcError> {
-        use irc::client::prelude::*;
+    pub fn new(
+        login: Login,
+        channels: &[String],
+        registry: Arc<VoteSystemRegistry>,
+        tx_control: MediatorControlSender,
+        moderation: ModerationConfig,
+        connection: ConnectionConfig,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        let (nickname, password) = match login {
+            // Twitch lets any justinfan in, without a password.
+            Login::Anonymous => (
+                format!("justinfan{}", rand::thread_rng().gen_range(1000, 100_000)),
+                None,
+            ),
+            Login::Account {
+                username,
+                oauth_token,
+            } => (username, Some(oauth_token)),
+        };
+        let channels = channels
+            .iter()
+            .map(|channel| format!("#{}", channel.trim_start_matches('#').to_lowercase()))
+            .collect();
 
-        let config = Config {
-            nickname: Some(username.clone()),
+        let irc_config = Config {
+            nickname: Some(nickname),
             server: Some("irc.chat.twitch.tv".to_owned()),
             port: Some(6697),
-            password: Some(oauth_token),
-            channels: Some(vec![format!("#{}", username).to_owned()]),
+            password,
+            channels: Some(channels),
             use_ssl: Some(true),
             ..Default::default()
         };
 
-        let client = IrcClient::from_config(config)?;
-        client.identify()?;
+        let input = IrcInput::with_irc_config(
+            irc_config, connection, registry, tx_control, moderation, recorder,
+        )
+        .on_twitch();
 
-        Ok(TwitchInput { client })
+        TwitchInput { input }
+    }
+
+    // Names the source Twitch's bans are scoped to, among several.
+    pub fn for_source(self, source: &str) -> Self {
+        TwitchInput {
+            input: self.input.for_source(source),
+        }
     }
 }
 
 impl CommandInput for TwitchInput {
     fn create_receiver(&self) -> Receiver<Input> {
-        use irc::client::prelude::Command as IrcCommand;
-        use irc::client::Client;
-
-        let (tx, rx) = channel();
-        let thread_client = self.client.clone();
-        thread::spawn(move || {
-            thread_client
-                .for_each_incoming(|message| {
-                    println!("twitch_input: {:?}", message);
-
-                    if let IrcCommand::PRIVMSG(ref _target, ref msg) = message.command {
-                        if let Some(command) = Command::from_string(msg.to_owned()) {
-                            let user = message
-                                .source_nickname()
-                                .unwrap_or("unknown user")
-                                .to_owned();
-                            println!("twitch_input: got {:?} command, from {}.", command, user);
-
-                            tx.send(Input(command, user)).unwrap();
-                        }
-                    }
-                })
-                .unwrap();
-        });
-
-        rx
+        self.input.create_receiver()
+    }
+
+    fn connection_states(&self) -> Option<Receiver<ConnectionState>> {
+        self.input.connection_states()
     }
 }
@@ -1,15 +1,24 @@
-use crate::command::Command;
-use crate::command_input::Input;
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+use crate::command_input::{ConnectionState, Input, User};
 use crate::command_output::CommandOutput;
-use crate::mediator::{MediatedDecision, MediatorUpdate, MediatorUpdateReceiver};
+use crate::idle::IdleStatus;
+use crate::input_filter::{DroppedInput, FilterCounter};
+use crate::latency::LatencyTracker;
+use crate::logging::logs_inputs;
+use crate::mediator::{MediatedDecision, MediatorUpdate, MediatorUpdateReceiver, PauseStatus};
+use crate::modifier::{ActiveModifier, Modifiers};
 use crate::renderer::Renderer;
-use crate::vote_system::VoteSystem;
-use stats::Frequencies;
+use crate::scheduler::VoteSystemLock;
+use crate::vote_system::{VoteSystem, VoteSystemSnapshot};
+use std::sync::mpsc::RecvTimeoutError;
 
 pub struct Control<O: CommandOutput, R: Renderer> {
     rx_update: MediatorUpdateReceiver,
     output: O,
     renderer: R,
+    modifiers: Modifiers,
+    latency: LatencyTracker,
 }
 
 impl<O, R> Control<O, R>
@@ -17,21 +26,38 @@ where
     O: CommandOutput,
     R: Renderer,
 {
-    pub fn new(rx_update: MediatorUpdateReceiver, output: O, renderer: R) -> Self {
+    pub fn new(
+        rx_update: MediatorUpdateReceiver,
+        output: O,
+        renderer: R,
+        modifiers: Modifiers,
+        clock: SharedClock,
+    ) -> Self {
         Control {
             rx_update,
             output,
             renderer,
+            modifiers,
+            latency: LatencyTracker::new(clock),
         }
     }
 
+    fn emit(&mut self, button: Button) {
+        self.output.emit(button);
+        self.renderer.new_command(Command::Action(button));
+    }
+
     fn on_decision(&mut self, decision: MediatedDecision) {
         match decision {
             MediatedDecision::Command(cmd) => match cmd {
                 Command::Action(button) => {
-                    println!("control: got {:?} command from Mediator.", button);
-                    self.output.emit(button);
-                    self.renderer.new_command(cmd);
+                    if logs_inputs() {
+                        println!("control: got {:?} command from Mediator.", button);
+                    }
+
+                    if let Some(button) = self.modifiers.apply(button) {
+                        self.emit(button);
+                    }
                 }
                 x => {
                     unreachable!(format!(
@@ -43,9 +69,9 @@ where
         }
     }
 
-    fn on_vote_system_percentage_change(&mut self, pct: Option<f64>) {
-        println!("control: got {:?} VoteSystemPercentageChange", pct);
-        self.renderer.new_vote_system_percentage(pct);
+    fn on_vote_system_votes_change(&mut self, votes: Vec<(VoteSystem, u64)>) {
+        println!("control: got {:?} VoteSystemVotesChange", votes);
+        self.renderer.new_vote_system_votes(votes);
     }
 
     fn on_vote_system_change(&mut self, system: VoteSystem) {
@@ -53,9 +79,45 @@ where
         self.renderer.new_vote_system(system);
     }
 
-    fn on_vote_system_partial_results(&mut self, t: u64, results: Frequencies<Command>) {
-        self.renderer
-            .new_vote_system_democracy_partial_results(t, results);
+    fn on_vote_system_lock_change(&mut self, lock: Option<VoteSystemLock>) {
+        println!("control: got {:?} VoteSystemLockChange", lock);
+        self.renderer.new_vote_system_lock(lock);
+    }
+
+    fn on_vote_system_snapshot(&mut self, snapshot: VoteSystemSnapshot) {
+        self.renderer.new_vote_system_snapshot(snapshot);
+    }
+
+    fn on_vote_system_round_cancelled(&mut self, votes: u64) {
+        println!("control: got {} VoteSystemRoundCancelled", votes);
+        self.renderer.new_vote_system_round_cancelled(votes);
+    }
+
+    fn on_idle_change(&mut self, status: Option<IdleStatus>) {
+        println!("control: got {:?} IdleChange", status);
+        self.renderer.new_idle_status(status);
+    }
+
+    fn on_pause_change(&mut self, status: Option<PauseStatus>) {
+        println!("control: got {:?} PauseChange", status);
+        self.renderer.new_pause_status(status);
+    }
+
+    fn on_modifier_activated(&mut self, active: ActiveModifier) {
+        println!("control: got {:?} ModifierActivated", active);
+        self.modifiers.activate(active);
+        self.renderer.new_modifiers(self.modifiers.active());
+    }
+
+    // Presses the delayed buttons that are due, and drops the modifiers that ran out.
+    fn update_modifiers(&mut self) {
+        for button in self.modifiers.take_delayed() {
+            self.emit(button);
+        }
+
+        if self.modifiers.expire() {
+            self.renderer.new_modifiers(self.modifiers.active());
+        }
     }
 
     fn on_vote_system_change_secs_remaining(&mut self, t: u64) {
@@ -63,26 +125,83 @@ where
     }
 
     fn on_input(&mut self, input: Input) {
-        println!("control: got {:?} Input", input);
+        if logs_inputs() {
+            println!("control: got {:?} Input", input);
+        }
+
+        self.latency.record(input.1.sent_at);
         self.renderer.new_input(input);
     }
 
+    fn report_latency(&mut self) {
+        if let Some(latency) = self.latency.report() {
+            println!("control: got {:?} InputLatency", latency);
+            self.renderer.new_input_latency(latency);
+        }
+    }
+
+    fn on_input_dropped(&mut self, dropped: DroppedInput) {
+        if logs_inputs() {
+            println!("control: got {:?} InputDropped", dropped);
+        }
+        self.renderer.new_dropped_input(dropped);
+    }
+
+    fn on_user_flagged(&mut self, user: User) {
+        println!("control: got {} UserFlagged", user);
+        self.renderer.new_flagged_user(user);
+    }
+
+    fn on_filter_counters_change(&mut self, counters: Vec<FilterCounter>) {
+        self.renderer.new_filter_counters(counters);
+    }
+
+    fn on_connection_change(&mut self, state: ConnectionState) {
+        println!("control: got {:?} ConnectionChange", state);
+        self.renderer.new_connection_state(state);
+    }
+
     pub fn run(&mut self) {
         loop {
             use crate::mediator::MediatorUpdate::*;
 
-            let update = self.rx_update.recv().unwrap();
+            // Wakes up for the delayed buttons, and to report the latency on time.
+            let timeout = match self.modifiers.next_delayed() {
+                Some(delayed) => delayed.min(self.latency.next_report()),
+                None => self.latency.next_report(),
+            };
+            let update = match self.rx_update.recv_timeout(timeout) {
+                Ok(update) => Some(update),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(e) => panic!("control: got {} err", e),
+            };
+
+            self.update_modifiers();
+            self.report_latency();
+
+            let update = match update {
+                Some(update) => update,
+                None => continue,
+            };
+
             match update {
                 Decision(decision) => self.on_decision(decision),
-                VoteSystemPercentageChange(p) => self.on_vote_system_percentage_change(p),
+                VoteSystemVotesChange(votes) => self.on_vote_system_votes_change(votes),
                 VoteSystemChange(system) => self.on_vote_system_change(system),
                 VoteSystemChangeSecsRemaining(secs) => {
                     self.on_vote_system_change_secs_remaining(secs)
                 }
-                VoteSystemDemocracyPartialResults(t, partial) => {
-                    self.on_vote_system_partial_results(t, partial)
-                }
+                VoteSystemLockChange(lock) => self.on_vote_system_lock_change(lock),
+                VoteSystemSnapshot(snapshot) => self.on_vote_system_snapshot(snapshot),
+                VoteSystemRoundCancelled(votes) => self.on_vote_system_round_cancelled(votes),
+                IdleChange(status) => self.on_idle_change(status),
+                ModifierActivated(active) => self.on_modifier_activated(active),
+                PauseChange(status) => self.on_pause_change(status),
                 Input(input) => self.on_input(input),
+                InputDropped(dropped) => self.on_input_dropped(dropped),
+                UserFlagged(user) => self.on_user_flagged(user),
+                FilterCountersChange(counters) => self.on_filter_counters_change(counters),
+                ConnectionChange(state) => self.on_connection_change(state),
             }
         }
     }
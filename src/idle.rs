@@ -0,0 +1,96 @@
+use crate::command::Button;
+use crate::vote_system::VoteSystemRegistry;
+
+use serde::{Deserialize, Serialize};
+
+// What to do once chat has been silent for a while.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    // Switches to another vote system, until chat comes back.
+    VoteSystem(String),
+    // Presses the buttons in order, one each interval, over and over.
+    Macro(Vec<Button>),
+    // Presses a single button each interval.
+    KeepAlive(Button),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct IdleConfig {
+    pub after_secs: u64,
+    pub interval_secs: u64,
+    // Without an action, idling does nothing.
+    pub action: Option<IdleAction>,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        IdleConfig {
+            after_secs: 300,
+            interval_secs: 5,
+            action: None,
+        }
+    }
+}
+
+impl IdleConfig {
+    pub fn validate(&self, registry: &VoteSystemRegistry) -> Result<(), String> {
+        match self.action {
+            Some(IdleAction::VoteSystem(ref name)) if registry.get(name).is_none() => {
+                Err(format!("idle: unknown vote system {}", name))
+            }
+            Some(IdleAction::Macro(ref buttons)) if buttons.is_empty() => {
+                Err("idle: the macro has no buttons".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // The buttons pressed while idle, if any.
+    pub fn buttons(&self) -> Vec<Button> {
+        match self.action {
+            Some(IdleAction::Macro(ref buttons)) => buttons.clone(),
+            Some(IdleAction::KeepAlive(button)) => vec![button],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct IdleStatus {
+    // Unix timestamp, in seconds.
+    pub since: u64,
+    pub action: IdleAction,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unknown_buttons_and_vote_systems() {
+        let registry = VoteSystemRegistry::with_builtin_systems(&Default::default());
+        let config = |action: &str| -> Result<IdleConfig, _> {
+            serde_json::from_str(&format!(r#"{{"action": {}}}"#, action))
+        };
+
+        let config_macro = config(r#"{"macro": ["up", "a"]}"#).unwrap();
+        assert_eq!(config_macro.buttons(), vec![Button::Up, Button::A]);
+        assert!(config_macro.validate(&registry).is_ok());
+
+        assert!(config(r#"{"keep_alive": "jump"}"#).is_err());
+        assert!(config(r#"{"macro": []}"#)
+            .unwrap()
+            .validate(&registry)
+            .is_err());
+        assert!(config(r#"{"vote_system": "Democracy"}"#)
+            .unwrap()
+            .validate(&registry)
+            .is_ok());
+        assert!(config(r#"{"vote_system": "Democrazy"}"#)
+            .unwrap()
+            .validate(&registry)
+            .is_err());
+    }
+}
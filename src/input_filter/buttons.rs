@@ -0,0 +1,53 @@
+use super::{DropReason, FilterResult, InputFilter};
+use crate::command::{Button, Command};
+use crate::command_input::Input;
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ButtonFilterConfig {
+    // Buttons chat can't press, like start during a tricky segment.
+    pub blocked: Vec<Button>,
+}
+
+// Drops blocked buttons. Ballots keep their other choices.
+pub struct ButtonFilter {
+    config: ButtonFilterConfig,
+}
+
+impl ButtonFilter {
+    pub fn new(config: ButtonFilterConfig) -> Self {
+        ButtonFilter { config }
+    }
+}
+
+impl InputFilter for ButtonFilter {
+    fn name(&self) -> &'static str {
+        "buttons"
+    }
+
+    fn filter(&mut self, input: &Input) -> FilterResult {
+        let blocked = &self.config.blocked;
+
+        match input.0 {
+            Command::Action(button) if blocked.contains(&button) => {
+                FilterResult::Drop(DropReason::BlockedButton)
+            }
+            Command::Ballot(ref buttons) if buttons.iter().any(|b| blocked.contains(b)) => {
+                let allowed: Vec<Button> = buttons
+                    .iter()
+                    .filter(|b| !blocked.contains(b))
+                    .cloned()
+                    .collect();
+
+                if allowed.is_empty() {
+                    FilterResult::Drop(DropReason::BlockedButton)
+                } else {
+                    FilterResult::Transform(Input(Command::Ballot(allowed), input.1.clone()))
+                }
+            }
+            _ => FilterResult::Accept,
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::clock::SharedClock;
+use crate::command_input::{Input, User};
+use crate::user_list::UserList;
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    // Banned or ignored.
+    Banned,
+    RateLimited,
+    Repeated,
+    BlockedButton,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DroppedInput {
+    pub input: Input,
+    pub reason: DropReason,
+}
+
+// Inputs are passed around by value everywhere else too, so they aren't boxed here.
+#[allow(clippy::large_enum_variant)]
+pub enum FilterResult {
+    Accept,
+    Drop(DropReason),
+    // Accepts a changed input, in place of the original.
+    Transform(Input),
+}
+
+// A stage inputs go through, on their way from chat to the vote systems.
+pub trait InputFilter: Send {
+    fn name(&self) -> &'static str;
+    fn filter(&mut self, input: &Input) -> FilterResult;
+
+    // Users that started looking suspicious since the last call.
+    fn take_flagged(&mut self) -> Vec<User> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FilterConfig {
+    Users,
+    Normalize,
+    Spam(SpamFilterConfig),
+    Buttons(ButtonFilterConfig),
+}
+
+impl FilterConfig {
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            FilterConfig::Users,
+            FilterConfig::Spam(SpamFilterConfig::default()),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FilterCounter {
+    pub name: &'static str,
+    pub transformed: u64,
+    pub dropped: u64,
+}
+
+// The configured filters, in order.
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn InputFilter>>,
+    counters: Vec<FilterCounter>,
+    counters_changed: bool,
+}
+
+impl FilterPipeline {
+    pub fn new(configs: &[FilterConfig], user_list: &Arc<UserList>, clock: &SharedClock) -> Self {
+        let mut configs = configs.to_vec();
+        // Bans and ignores always apply, first unless they're placed elsewhere.
+        if !configs
+            .iter()
+            .any(|config| matches!(config, FilterConfig::Users))
+        {
+            println!("FilterPipeline::new: no users filter, running it first");
+            configs.insert(0, FilterConfig::Users);
+        }
+
+        let filters: Vec<Box<dyn InputFilter>> = configs
+            .iter()
+            .map(|config| -> Box<dyn InputFilter> {
+                match config {
+                    FilterConfig::Users => Box::new(UserListFilter::new(user_list.clone())),
+                    FilterConfig::Normalize => Box::new(NormalizeFilter {}),
+                    FilterConfig::Spam(config) => {
+                        Box::new(SpamFilter::new(config.clone(), clock.clone()))
+                    }
+                    FilterConfig::Buttons(config) => Box::new(ButtonFilter::new(config.clone())),
+                }
+            })
+            .collect();
+        let counters = filters
+            .iter()
+            .map(|filter| FilterCounter {
+                name: filter.name(),
+                transformed: 0,
+                dropped: 0,
+            })
+            .collect();
+
+        FilterPipeline {
+            filters,
+            counters,
+            counters_changed: false,
+        }
+    }
+
+    // Runs the input through every filter, until one drops it.
+    #[allow(clippy::result_large_err)]
+    pub fn run(&mut self, input: Input) -> Result<Input, DroppedInput> {
+        let mut input = input;
+
+        for (filter, counter) in self.filters.iter_mut().zip(self.counters.iter_mut()) {
+            match filter.filter(&input) {
+                FilterResult::Accept => (),
+                FilterResult::Transform(transformed) => {
+                    counter.transformed += 1;
+                    self.counters_changed = true;
+                    input = transformed;
+                }
+                FilterResult::Drop(reason) => {
+                    counter.dropped += 1;
+                    self.counters_changed = true;
+                    return Err(DroppedInput { input, reason });
+                }
+            }
+        }
+
+        Ok(input)
+    }
+
+    pub fn take_flagged(&mut self) -> Vec<User> {
+        self.filters
+            .iter_mut()
+            .flat_map(|filter| filter.take_flagged())
+            .collect()
+    }
+
+    // The counters, if they changed since the last call.
+    pub fn take_counters(&mut self) -> Option<Vec<FilterCounter>> {
+        if !self.counters_changed {
+            return None;
+        }
+
+        self.counters_changed = false;
+        Some(self.counters.clone())
+    }
+}
+
+pub mod buttons;
+pub use buttons::{ButtonFilter, ButtonFilterConfig};
+
+pub mod normalize;
+pub use normalize::NormalizeFilter;
+
+pub mod spam;
+pub use spam::{LegacySpamConfig, SpamFilter, SpamFilterConfig};
+
+pub mod users;
+pub use users::UserListFilter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::command::{Button, Command};
+    use crate::command_input::Chatter;
+    use crate::user_list::UserListConfig;
+
+    #[test]
+    fn runs_filters_in_order_and_counts_what_they_did() {
+        let clock: SharedClock = Arc::new(ManualClock::new());
+        let user_list = Arc::new(UserList::load(
+            &UserListConfig { path: None },
+            clock.clone(),
+        ));
+        user_list.ban("troll", None);
+        let configs = vec![
+            FilterConfig::Normalize,
+            FilterConfig::Buttons(ButtonFilterConfig {
+                blocked: vec![Button::Start],
+            }),
+            FilterConfig::Users,
+        ];
+        let mut pipeline = FilterPipeline::new(&configs, &user_list, &clock);
+        let ballot = |buttons: &[Button], user: &str| {
+            Input(Command::Ballot(buttons.to_vec()), Chatter::new(user))
+        };
+
+        assert_eq!(
+            pipeline
+                .run(ballot(&[Button::A, Button::Start, Button::A], "User"))
+                .ok(),
+            Some(ballot(&[Button::A], "user"))
+        );
+        assert_eq!(pipeline.take_counters().unwrap().len(), 3);
+        assert!(pipeline.take_counters().is_none());
+
+        let dropped = pipeline
+            .run(Input(Command::Action(Button::Start), Chatter::new("user")))
+            .unwrap_err();
+        assert_eq!(dropped.reason, DropReason::BlockedButton);
+
+        let dropped = pipeline.run(ballot(&[Button::B], "Troll")).unwrap_err();
+        assert_eq!(dropped.reason, DropReason::Banned);
+
+        let counters: Vec<(&str, u64, u64)> = pipeline
+            .take_counters()
+            .unwrap()
+            .iter()
+            .map(|c| (c.name, c.transformed, c.dropped))
+            .collect();
+        assert_eq!(
+            counters,
+            vec![("normalize", 2, 0), ("buttons", 1, 1), ("users", 0, 1)]
+        );
+    }
+
+    #[test]
+    fn bans_apply_without_a_users_filter() {
+        let clock: SharedClock = Arc::new(ManualClock::new());
+        let user_list = Arc::new(UserList::load(
+            &UserListConfig { path: None },
+            clock.clone(),
+        ));
+        user_list.ban("troll", None);
+        let mut pipeline = FilterPipeline::new(&[FilterConfig::Normalize], &user_list, &clock);
+
+        let dropped = pipeline
+            .run(Input(Command::Action(Button::A), Chatter::new("troll")))
+            .unwrap_err();
+        assert_eq!(dropped.reason, DropReason::Banned);
+
+        let names: Vec<&str> = pipeline
+            .take_counters()
+            .unwrap()
+            .iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["users", "normalize"]);
+    }
+
+    #[test]
+    fn scopes_bans_and_rate_limits_to_a_source() {
+        let clock: SharedClock = Arc::new(ManualClock::new());
+        let user_list = Arc::new(UserList::load(
+            &UserListConfig { path: None },
+            clock.clone(),
+        ));
+        user_list.ban("irc:troll", None);
+        let spam = SpamFilterConfig {
+            max_inputs: Some(1),
+            ..SpamFilterConfig::default()
+        };
+        let mut pipeline = FilterPipeline::new(&[FilterConfig::Spam(spam)], &user_list, &clock);
+        let press = |source: &str| {
+            let chatter = Chatter {
+                source: Some(source.to_owned()),
+                ..Chatter::new("troll")
+            };
+            Input(Command::Action(Button::A), chatter)
+        };
+
+        assert!(pipeline.run(press("twitch")).is_ok());
+        let dropped = pipeline.run(press("irc")).unwrap_err();
+        assert_eq!(dropped.reason, DropReason::Banned);
+        let dropped = pipeline.run(press("twitch")).unwrap_err();
+        assert_eq!(dropped.reason, DropReason::RateLimited);
+
+        user_list.unban("irc:troll");
+        assert!(pipeline.run(press("irc")).is_ok());
+    }
+}
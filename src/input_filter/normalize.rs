@@ -0,0 +1,42 @@
+use super::{FilterResult, InputFilter};
+use crate::command::Command;
+use crate::command_input::{Chatter, Input};
+
+// Lowercases user names, so later stages see a single user however they're typed, and
+// drops repeated buttons from ballots.
+pub struct NormalizeFilter {}
+
+impl InputFilter for NormalizeFilter {
+    fn name(&self) -> &'static str {
+        "normalize"
+    }
+
+    fn filter(&mut self, input: &Input) -> FilterResult {
+        let Input(ref command, ref chatter) = *input;
+
+        let command = match command {
+            Command::Ballot(buttons) => {
+                let mut unique = Vec::new();
+                for button in buttons {
+                    if !unique.contains(button) {
+                        unique.push(*button);
+                    }
+                }
+
+                Command::Ballot(unique)
+            }
+            other => other.clone(),
+        };
+        let chatter = Chatter {
+            name: chatter.name.to_lowercase(),
+            ..chatter.clone()
+        };
+        let normalized = Input(command, chatter);
+
+        if normalized == *input {
+            FilterResult::Accept
+        } else {
+            FilterResult::Transform(normalized)
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use super::{DropReason, FilterResult, InputFilter};
+use crate::clock::SharedClock;
+use crate::command::Command;
+use crate::command_input::{Chatter, Input, User};
+
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SpamFilterConfig {
+    // Inputs each user can send within the window. Without it, there's no rate limit.
+    pub max_inputs: Option<usize>,
+    pub window_ms: u64,
+    // The same input this many times in a row, and the rest are dropped until it changes.
+    pub max_repeats: usize,
+    // Users get flagged once this many of their inputs were dropped.
+    pub flag_after: u64,
+}
+
+impl Default for SpamFilterConfig {
+    fn default() -> Self {
+        SpamFilterConfig {
+            max_inputs: None,
+            window_ms: 1000,
+            max_repeats: 10,
+            flag_after: 50,
+        }
+    }
+}
+
+// How [mediator.spam] looked before there were filters, rate limited by default.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LegacySpamConfig {
+    pub max_inputs: usize,
+    pub window_ms: u64,
+    pub max_repeats: usize,
+    pub flag_after: u64,
+}
+
+impl Default for LegacySpamConfig {
+    fn default() -> Self {
+        LegacySpamConfig {
+            max_inputs: 3,
+            window_ms: 1000,
+            max_repeats: 10,
+            flag_after: 50,
+        }
+    }
+}
+
+impl From<LegacySpamConfig> for SpamFilterConfig {
+    fn from(legacy: LegacySpamConfig) -> Self {
+        SpamFilterConfig {
+            max_inputs: Some(legacy.max_inputs),
+            window_ms: legacy.window_ms,
+            max_repeats: legacy.max_repeats,
+            flag_after: legacy.flag_after,
+        }
+    }
+}
+
+struct Activity {
+    // When the inputs within the window were sent.
+    recent: VecDeque<Instant>,
+    last_seen: Instant,
+    last_command: Option<Command>,
+    repeats: usize,
+    dropped: u64,
+}
+
+// Keeps each user's inputs at a sane pace, before they reach the vote systems.
+pub struct SpamFilter {
+    config: SpamFilterConfig,
+    clock: SharedClock,
+    // By source and user, since the same name on another source may be someone else.
+    activity: HashMap<(Option<String>, User), Activity>,
+    // When chatters that went quiet were last forgotten.
+    last_sweep: Instant,
+    flagged: Vec<User>,
+}
+
+impl SpamFilter {
+    pub fn new(config: SpamFilterConfig, clock: SharedClock) -> Self {
+        let last_sweep = clock.now();
+
+        SpamFilter {
+            config,
+            clock,
+            activity: HashMap::new(),
+            last_sweep,
+            flagged: Vec::new(),
+        }
+    }
+
+    // Forgets the chatters that sent nothing for a whole window, at most once per window.
+    fn sweep(&mut self, now: Instant, window: Duration) {
+        if now.duration_since(self.last_sweep) < window {
+            return;
+        }
+
+        self.activity
+            .retain(|_, activity| now.duration_since(activity.last_seen) < window);
+        self.last_sweep = now;
+    }
+}
+
+impl InputFilter for SpamFilter {
+    fn name(&self) -> &'static str {
+        "spam"
+    }
+
+    fn filter(&mut self, input: &Input) -> FilterResult {
+        let Input(
+            ref command,
+            Chatter {
+                name: ref user,
+                ref source,
+                ..
+            },
+        ) = *input;
+        let now = self.clock.now();
+        let window = Duration::from_millis(self.config.window_ms);
+        self.sweep(now, window);
+
+        let activity = self
+            .activity
+            .entry((source.clone(), user.to_lowercase()))
+            .or_insert_with(|| Activity {
+                recent: VecDeque::new(),
+                last_seen: now,
+                last_command: None,
+                repeats: 0,
+                dropped: 0,
+            });
+        activity.last_seen = now;
+
+        while let Some(&first) = activity.recent.front() {
+            if now.duration_since(first) < window {
+                break;
+            }
+
+            activity.recent.pop_front();
+        }
+
+        let repeats = if activity.last_command.as_ref() == Some(command) {
+            activity.repeats + 1
+        } else {
+            1
+        };
+
+        let limited = match self.config.max_inputs {
+            Some(max_inputs) => activity.recent.len() >= max_inputs,
+            None => false,
+        };
+
+        let reason = if limited {
+            DropReason::RateLimited
+        } else if repeats > self.config.max_repeats {
+            DropReason::Repeated
+        } else {
+            activity.recent.push_back(now);
+            activity.last_command = Some(command.clone());
+            activity.repeats = repeats;
+
+            return FilterResult::Accept;
+        };
+
+        activity.dropped += 1;
+        if activity.dropped == self.config.flag_after {
+            self.flagged.push(user.clone());
+        }
+
+        FilterResult::Drop(reason)
+    }
+
+    fn take_flagged(&mut self) -> Vec<User> {
+        std::mem::take(&mut self.flagged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::command::Button;
+    use std::sync::Arc;
+
+    fn press(filter: &mut SpamFilter, user: &str, button: Button) -> Option<DropReason> {
+        match filter.filter(&Input(Command::Action(button), Chatter::new(user))) {
+            FilterResult::Drop(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn limits_the_rate_only_when_asked_and_forgets_quiet_chatters() {
+        let clock = Arc::new(ManualClock::new());
+        let mut filter = SpamFilter::new(SpamFilterConfig::default(), clock.clone());
+
+        // Repeats are limited by default, but not the rate.
+        for _ in 0..10 {
+            assert_eq!(press(&mut filter, "alice", Button::A), None);
+        }
+        assert_eq!(
+            press(&mut filter, "alice", Button::A),
+            Some(DropReason::Repeated)
+        );
+        assert_eq!(press(&mut filter, "alice", Button::B), None);
+
+        let config = SpamFilterConfig {
+            max_inputs: Some(2),
+            ..SpamFilterConfig::default()
+        };
+        let mut filter = SpamFilter::new(config, clock.clone());
+        assert_eq!(press(&mut filter, "bob", Button::A), None);
+        assert_eq!(press(&mut filter, "bob", Button::B), None);
+        assert_eq!(
+            press(&mut filter, "bob", Button::A),
+            Some(DropReason::RateLimited)
+        );
+        assert_eq!(press(&mut filter, "carol", Button::A), None);
+        assert_eq!(filter.activity.len(), 2);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(press(&mut filter, "bob", Button::A), None);
+        let users: Vec<&str> = filter
+            .activity
+            .keys()
+            .map(|(_, user)| user.as_str())
+            .collect();
+        assert_eq!(users, vec!["bob"]);
+        assert_eq!(filter.last_sweep, clock.now());
+    }
+}
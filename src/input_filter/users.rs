@@ -0,0 +1,40 @@
+use super::{DropReason, FilterResult, InputFilter};
+use crate::command_input::Input;
+use crate::user_list::UserList;
+
+use std::sync::Arc;
+
+// Drops inputs from banned and ignored users. Names can be scoped to a source, like
+// "irc:someone", since the same name on another source may be someone else.
+pub struct UserListFilter {
+    user_list: Arc<UserList>,
+}
+
+impl UserListFilter {
+    pub fn new(user_list: Arc<UserList>) -> Self {
+        UserListFilter { user_list }
+    }
+}
+
+impl InputFilter for UserListFilter {
+    fn name(&self) -> &'static str {
+        "users"
+    }
+
+    fn filter(&mut self, input: &Input) -> FilterResult {
+        let chatter = &input.1;
+        let allowed = self.user_list.allows(&chatter.name)
+            && match chatter.source {
+                Some(ref source) => self
+                    .user_list
+                    .allows(&format!("{}:{}", source, chatter.name)),
+                None => true,
+            };
+
+        if allowed {
+            FilterResult::Accept
+        } else {
+            FilterResult::Drop(DropReason::Banned)
+        }
+    }
+}
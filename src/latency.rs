@@ -0,0 +1,120 @@
+use crate::clock::SharedClock;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// How long inputs are gathered before reporting on them.
+const WINDOW: Duration = Duration::from_secs(10);
+
+// How the inputs of the last window got from chat to control.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct InputLatency {
+    pub inputs: u64,
+    pub inputs_per_sec: f64,
+    // None when no input told when it was sent.
+    pub p50_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+}
+
+// Measures the time since inputs were sent, as told by the chat server or the load input.
+pub struct LatencyTracker {
+    clock: SharedClock,
+    window_start: Instant,
+    inputs: u64,
+    samples: Vec<u64>,
+}
+
+impl LatencyTracker {
+    pub fn new(clock: SharedClock) -> Self {
+        let window_start = clock.now();
+
+        LatencyTracker {
+            clock,
+            window_start,
+            inputs: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, sent_at: Option<u64>) {
+        self.inputs += 1;
+        if let Some(sent_at) = sent_at {
+            self.samples
+                .push(self.clock.unix_now_ms().saturating_sub(sent_at));
+        }
+    }
+
+    // How long until the window is over, so it's reported even when chat goes quiet.
+    pub fn next_report(&self) -> Duration {
+        WINDOW
+            .checked_sub(self.clock.elapsed(self.window_start))
+            .unwrap_or(Duration::from_secs(0))
+    }
+
+    // Reports on the window once it's over.
+    pub fn report(&mut self) -> Option<InputLatency> {
+        let elapsed = self.clock.elapsed(self.window_start);
+        if elapsed < WINDOW {
+            return None;
+        }
+
+        self.samples.sort_unstable();
+        let samples = &self.samples;
+        let percentile = |p: f64| {
+            let rank = (p * samples.len() as f64).ceil() as usize;
+            samples.get(rank.saturating_sub(1)).copied()
+        };
+        let latency = InputLatency {
+            inputs: self.inputs,
+            inputs_per_sec: self.inputs as f64 / elapsed.as_secs_f64(),
+            p50_ms: percentile(0.5),
+            p99_ms: percentile(0.99),
+            max_ms: samples.last().copied(),
+        };
+
+        self.window_start = self.clock.now();
+        self.inputs = 0;
+        self.samples.clear();
+
+        Some(latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use std::sync::Arc;
+
+    #[test]
+    fn reports_percentiles_once_the_window_is_over() {
+        let clock = Arc::new(ManualClock::new());
+        let mut tracker = LatencyTracker::new(clock.clone());
+
+        clock.advance(Duration::from_secs(1));
+        for ms in 1..=100 {
+            tracker.record(Some(clock.unix_now_ms() - ms));
+        }
+        tracker.record(None);
+        assert_eq!(tracker.report(), None);
+        assert_eq!(tracker.next_report(), Duration::from_secs(9));
+
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(
+            tracker.report(),
+            Some(InputLatency {
+                inputs: 101,
+                inputs_per_sec: 10.1,
+                p50_ms: Some(50),
+                p99_ms: Some(99),
+                max_ms: Some(100),
+            })
+        );
+
+        // Quiet windows are reported too.
+        clock.advance(Duration::from_secs(10));
+        let latency = tracker.report().unwrap();
+        assert_eq!(latency.inputs, 0);
+        assert_eq!(latency.max_ms, None);
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Inputs are logged from every stage they go through, so whether to is kept for the
+// whole program instead of handed to each of them.
+static LOG_INPUTS: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoggingConfig {
+    // Logs every chat message, input and decision. Too much under heavy load.
+    pub inputs: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { inputs: true }
+    }
+}
+
+impl LoggingConfig {
+    pub fn apply(&self) {
+        LOG_INPUTS.store(self.inputs, Ordering::Relaxed);
+    }
+}
+
+pub fn logs_inputs() -> bool {
+    LOG_INPUTS.load(Ordering::Relaxed)
+}
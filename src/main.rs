@@ -10,7 +10,10 @@ extern crate serde_json;
 mod command;
 
 mod command_input;
-use command_input::{CommandInput, TwitchInput};
+use command_input::{
+    CommandInput, HttpInput, IrcInput, LoadInput, MultiInput, Recorder, ReplayInput, SourceConfig,
+    SourceKind, StdinInput, TcpInput, TwitchInput,
+};
 
 mod command_output;
 use command_output::KeyboardOutput;
@@ -19,11 +22,24 @@ mod renderer;
 use renderer::HTTPRenderer;
 
 mod vote_system;
-use vote_system::VoteSystem;
+use vote_system::{VoteSystem, VoteSystemRegistry};
 
+mod idle;
+mod input_filter;
+mod latency;
+mod logging;
 mod mediator;
+mod moderation;
+mod modifier;
+mod scheduler;
+mod user_list;
 mod vote_counter;
 use mediator::Mediator;
+use modifier::Modifiers;
+use user_list::UserList;
+
+mod clock;
+use clock::{SharedClock, SystemClock};
 
 mod control;
 use control::Control;
@@ -32,6 +48,8 @@ mod tpp_config;
 use tpp_config::TPPConfig;
 
 use irc::client::prelude::*;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
 
 fn main() {
     let mut settings = config::Config::default();
@@ -42,15 +60,154 @@ fn main() {
         .unwrap();
 
     let tpp_config = settings.try_into::<TPPConfig>().unwrap();
-
-    let twitch_input = TwitchInput::new(tpp_config.username, tpp_config.oauth_token).unwrap();
+    tpp_config.logging.apply();
+
+    let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(
+        &tpp_config.vote_systems,
+    ));
+    tpp_config.vote_systems.validate().unwrap();
+    tpp_config.moderation.validate().unwrap();
+    SourceConfig::validate_all(&tpp_config.inputs).unwrap();
+    tpp_config.recorder.validate(&tpp_config.inputs).unwrap();
+    tpp_config.mediator.idle.validate(&registry).unwrap();
+    for entry in &tpp_config.mediator.schedule {
+        entry.validate(&registry).unwrap();
+    }
+    let clock: SharedClock = Arc::new(SystemClock {});
+    let user_list = Arc::new(UserList::load(&tpp_config.users, clock.clone()));
+    let (tx_control, rx_control) = channel();
+    let recorder = tpp_config
+        .recorder
+        .path
+        .as_ref()
+        .map(|path| Recorder::create(path, clock.clone()).unwrap());
+    // Inputs the HTTP server hands posted inputs to.
+    let mut http_inputs = Vec::new();
+    // Sources are only named when there are several.
+    let mut connect = |source: Option<&str>, kind: SourceKind| -> Box<dyn CommandInput> {
+        match kind {
+            SourceKind::Twitch { channels } => {
+                let channels = if channels.is_empty() {
+                    tpp_config.channels().unwrap()
+                } else {
+                    channels
+                };
+
+                let input = TwitchInput::new(
+                    tpp_config.login().unwrap(),
+                    &channels,
+                    registry.clone(),
+                    tx_control.clone(),
+                    tpp_config.moderation.clone(),
+                    tpp_config.connection.clone(),
+                    recorder.clone(),
+                );
+
+                match source {
+                    Some(source) => Box::new(input.for_source(source)),
+                    None => Box::new(input),
+                }
+            }
+            SourceKind::Irc(irc_config) => Box::new(IrcInput::new(
+                irc_config,
+                registry.clone(),
+                tx_control.clone(),
+                tpp_config.moderation.clone(),
+                tpp_config.connection.clone(),
+                recorder.clone(),
+            )),
+            SourceKind::Stdin => Box::new(StdinInput::new(
+                registry.clone(),
+                tx_control.clone(),
+                tpp_config.moderation.clone(),
+                recorder.clone(),
+            )),
+            SourceKind::Tcp { address } => Box::new(
+                TcpInput::bind(
+                    &address,
+                    registry.clone(),
+                    tx_control.clone(),
+                    tpp_config.moderation.clone(),
+                    recorder.clone(),
+                )
+                .unwrap(),
+            ),
+            SourceKind::Http {
+                token,
+                websocket_address,
+            } => {
+                let input = HttpInput::new(
+                    token,
+                    websocket_address,
+                    registry.clone(),
+                    tx_control.clone(),
+                    tpp_config.moderation.clone(),
+                    recorder.clone(),
+                )
+                .unwrap();
+                http_inputs.push(input.sender());
+
+                Box::new(input)
+            }
+            SourceKind::Replay { path, speed } => Box::new(ReplayInput::new(
+                path,
+                speed,
+                registry.clone(),
+                tx_control.clone(),
+                tpp_config.moderation.clone(),
+                clock.clone(),
+            )),
+            SourceKind::Load(config) => {
+                Box::new(LoadInput::new(config, &registry, clock.clone()).unwrap())
+            }
+        }
+    };
+    let command_input: Box<dyn CommandInput> = if !tpp_config.inputs.is_empty() {
+        let sources = tpp_config
+            .inputs
+            .iter()
+            .map(|source| {
+                (
+                    source.name.clone(),
+                    connect(Some(&source.name), source.kind.clone()),
+                )
+            })
+            .collect();
+
+        Box::new(MultiInput::new(sources))
+    } else {
+        match tpp_config.irc {
+            Some(ref irc_config) => connect(None, SourceKind::Irc(irc_config.clone())),
+            None => connect(
+                None,
+                SourceKind::Twitch {
+                    channels: Vec::new(),
+                },
+            ),
+        }
+    };
+
+    let modifiers = Modifiers::new(tpp_config.mediator.modifiers.clone(), clock.clone());
 
     let keyboard_output = KeyboardOutput::new();
-    let http_renderer = HTTPRenderer::new();
+    let http_renderer = HTTPRenderer::new(
+        tpp_config.http.clone(),
+        tx_control,
+        user_list.clone(),
+        http_inputs,
+    );
     http_renderer.run_in_background();
 
-    let mediator = Mediator::create(twitch_input, VoteSystem::Anarchy);
-
-    let mut control = Control::new(mediator, keyboard_output, http_renderer);
+    let mediator = Mediator::create(
+        command_input,
+        VoteSystem::new("Anarchy"),
+        registry,
+        tpp_config.mediator,
+        rx_control,
+        user_list,
+        clock.clone(),
+    );
+
+    let mut control = Control::new(mediator, keyboard_output, http_renderer, modifiers, clock);
     control.run();
 }
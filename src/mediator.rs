@@ -1,19 +1,31 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::clock::SharedClock;
 use crate::command::{Button, Command};
-use crate::command_input::{CommandInput, Input};
+use crate::command_input::{CommandInput, ConnectionState, Input, User};
+use crate::idle::{IdleAction, IdleConfig, IdleStatus};
+use crate::input_filter::{
+    DroppedInput, FilterConfig, FilterCounter, FilterPipeline, LegacySpamConfig,
+};
+use crate::modifier::{ActiveModifier, ModifierVotes, ModifiersConfig};
+use crate::scheduler::{ScheduleEntry, ScheduledEntry, VoteSystemLock};
+use crate::user_list::UserList;
 use crate::vote_counter::VoteCounter;
 use crate::vote_system::{
-    VoteFunction, VoteSystem, VoteSystemCreator, VoteSystemUpdateReceiver, VoteSystemUpdateSender,
+    VoteFunction, VoteSystem, VoteSystemRegistry, VoteSystemSnapshot, VoteSystemUpdateReceiver,
+    VoteSystemUpdateSender,
 };
 
-use stats::Frequencies;
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
+// How many operator requests can be undone.
+const UNDO_LIMIT: usize = 50;
+
 pub enum MediatedDecision {
     Command(Command),
 }
@@ -21,22 +33,311 @@ pub enum MediatedDecision {
 pub enum MediatorUpdate {
     VoteSystemChange(VoteSystem),
     VoteSystemChangeSecsRemaining(u64),
-    VoteSystemPercentageChange(Option<f64>),
-    VoteSystemDemocracyPartialResults(u64, Frequencies<Command>),
+    VoteSystemVotesChange(Vec<(VoteSystem, u64)>),
+    VoteSystemLockChange(Option<VoteSystemLock>),
+    VoteSystemSnapshot(VoteSystemSnapshot),
+    // How many pending votes were lost when the vote system changed.
+    VoteSystemRoundCancelled(u64),
+    IdleChange(Option<IdleStatus>),
+    ModifierActivated(ActiveModifier),
+    PauseChange(Option<PauseStatus>),
     Input(Input),
+    // Inputs the spam filter kept from being counted.
+    InputDropped(DroppedInput),
+    UserFlagged(User),
+    FilterCountersChange(Vec<FilterCounter>),
+    ConnectionChange(ConnectionState),
     Decision(MediatedDecision),
 }
 
 pub type MediatorUpdateSender = Sender<MediatorUpdate>;
 pub type MediatorUpdateReceiver = Receiver<MediatorUpdate>;
 
+// Requests from the operators, through chat or the HTTP API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediatorControl {
+    Pause(Option<String>),
+    Resume,
+    ChangeVoteSystem(VoteSystem),
+    // Locks the current vote system, until it's unlocked.
+    Lock,
+    Unlock,
+    // Puts back a lock that was lifted, to undo an unlock. Can't be sent through chat.
+    Relock(VoteSystemLock),
+    // Bans for some seconds, or for good.
+    Ban(User, Option<u64>),
+    // Follows a ban or timeout from the chat service's own moderation. Only lasts the
+    // session and can't be undone. Can't be sent through chat.
+    ChatBan(User, Option<u64>),
+    Unban(User),
+    Ignore(User),
+    Unignore(User),
+    // Reverts the last request that changed anything.
+    Undo,
+}
+
+impl MediatorControl {
+    pub const NAMES: [&'static str; 10] = [
+        "pause", "resume", "mode", "lock", "unlock", "ban", "unban", "ignore", "unignore", "undo",
+    ];
+
+    // Chat commands, like "!pause fixing the emulator" or "!mode democracy".
+    pub fn from_string(s: &str, registry: &VoteSystemRegistry) -> Option<Self> {
+        use MediatorControl::*;
+
+        let mut parts = s.trim().splitn(2, ' ');
+        let command = parts.next()?;
+        let argument = parts.next().map(str::trim).filter(|arg| !arg.is_empty());
+        let user = |name: &str| name.trim_start_matches('@').to_lowercase();
+
+        match (command, argument) {
+            ("!pause", reason) => Some(Pause(reason.map(str::to_owned))),
+            ("!resume", None) => Some(Resume),
+            ("!mode", Some(name)) => registry
+                .by_keyword(name)
+                .or_else(|| registry.get(name))
+                .map(ChangeVoteSystem),
+            ("!lock", None) => Some(Lock),
+            ("!unlock", None) => Some(Unlock),
+            ("!ban", Some(args)) => {
+                let mut args = args.split_whitespace();
+                let name = user(args.next()?);
+
+                match args.next() {
+                    Some(duration) => Some(Ban(name, Some(parse_duration(duration)?))),
+                    None => Some(Ban(name, None)),
+                }
+            }
+            ("!unban", Some(name)) => Some(Unban(user(name))),
+            ("!ignore", Some(name)) => Some(Ignore(user(name))),
+            ("!unignore", Some(name)) => Some(Unignore(user(name))),
+            ("!undo", None) => Some(Undo),
+            _ => None,
+        }
+    }
+
+    // The name permissions are configured by.
+    pub fn name(&self) -> &'static str {
+        use MediatorControl::*;
+
+        match self {
+            Pause(_) => "pause",
+            Resume => "resume",
+            ChangeVoteSystem(_) => "mode",
+            Lock | Relock(_) => "lock",
+            Unlock => "unlock",
+            Ban(_, _) | ChatBan(_, _) => "ban",
+            Unban(_) => "unban",
+            Ignore(_) => "ignore",
+            Unignore(_) => "unignore",
+            Undo => "undo",
+        }
+    }
+}
+
+// "90", "90s", "15m", "2h" or "1d", in seconds.
+fn parse_duration(s: &str) -> Option<u64> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+
+    number.parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+pub type MediatorControlSender = Sender<MediatorControl>;
+pub type MediatorControlReceiver = Receiver<MediatorControl>;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PauseStatus {
+    pub reason: Option<String>,
+    // Unix timestamp, in seconds.
+    pub since: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct MediatorConfig {
+    pub schedule: Vec<ScheduleEntry>,
+    pub idle: IdleConfig,
+    pub modifiers: ModifiersConfig,
+    // Run in order, on every input.
+    pub filters: Vec<FilterConfig>,
+    // From before there were filters, in place of the spam filter.
+    pub spam: Option<LegacySpamConfig>,
+}
+
+impl MediatorConfig {
+    // The filters to run, with the old spam settings moved over.
+    pub fn filters(&self) -> Vec<FilterConfig> {
+        let mut filters = self.filters.clone();
+
+        if let Some(ref spam) = self.spam {
+            println!("mediator: [mediator.spam] is deprecated, use a spam filter instead");
+
+            let spam = FilterConfig::Spam(spam.clone().into());
+            match filters
+                .iter()
+                .position(|filter| matches!(filter, FilterConfig::Spam(_)))
+            {
+                Some(i) => filters[i] = spam,
+                None => filters.push(spam),
+            }
+        }
+
+        filters
+    }
+}
+
+impl Default for MediatorConfig {
+    fn default() -> Self {
+        MediatorConfig {
+            schedule: Vec::new(),
+            idle: IdleConfig::default(),
+            modifiers: ModifiersConfig::default(),
+            filters: FilterConfig::defaults(),
+            spam: None,
+        }
+    }
+}
+
+struct Running {
+    system: VoteSystem,
+    vote_fn: VoteFunction,
+}
+
+// The running vote system, and everything needed to replace it.
+#[derive(Clone)]
+struct ActiveVoteSystem {
+    running: Arc<Mutex<Running>>,
+    tx_decision: VoteSystemUpdateSender,
+    registry: Arc<VoteSystemRegistry>,
+    clock: SharedClock,
+    tx_mediator_update: MediatorUpdateSender,
+}
+
+impl ActiveVoteSystem {
+    fn call(&self, input: Input) {
+        self.running.lock().unwrap().vote_fn.call(input);
+    }
+
+    fn current(&self) -> VoteSystem {
+        self.running.lock().unwrap().system.clone()
+    }
+
+    // Swaps while holding the lock, so it can't be locked in between.
+    fn swap_unless_locked(&self, system: VoteSystem, system_lock: &Mutex<Option<VoteSystemLock>>) {
+        let _lock = system_lock.lock().unwrap();
+
+        if _lock.is_none() {
+            self.swap(system);
+        }
+    }
+
+    fn swap(&self, new_vote_system: VoteSystem) {
+        // Held until the swap is done, so concurrent swaps happen one after the other.
+        let mut _running = self.running.lock().unwrap();
+        if _running.system == new_vote_system {
+            return;
+        }
+
+        let vote_fn = match self.registry.create(
+            &new_vote_system,
+            self.tx_decision.clone(),
+            self.clock.clone(),
+        ) {
+            Some(vote_fn) => vote_fn,
+            None => {
+                println!(
+                    "ActiveVoteSystem::swap: {:?} is not registered",
+                    new_vote_system
+                );
+                return;
+            }
+        };
+
+        let pending = _running.vote_fn.on_deactivate();
+        let pending_votes = pending.len() as u64;
+        let cancelled = if vote_fn.on_activate(pending) {
+            0
+        } else {
+            pending_votes
+        };
+        *_running = Running {
+            system: new_vote_system.clone(),
+            vote_fn,
+        };
+
+        self.tx_mediator_update
+            .send(MediatorUpdate::VoteSystemChange(new_vote_system))
+            .unwrap();
+
+        if cancelled > 0 {
+            println!("ActiveVoteSystem::swap: {} pending votes lost", cancelled);
+            self.tx_mediator_update
+                .send(MediatorUpdate::VoteSystemRoundCancelled(cancelled))
+                .unwrap();
+        }
+    }
+
+    fn tick(&self) -> VoteSystemSnapshot {
+        let _running = self.running.lock().unwrap();
+        _running.vote_fn.tick();
+
+        _running.vote_fn.snapshot()
+    }
+}
+
+// Chat activity, for the idle policy.
+struct Idle {
+    last_input: Instant,
+    status: Option<IdleStatus>,
+    // The vote system that was replaced when chat went idle.
+    previous_system: Option<VoteSystem>,
+}
+
 pub struct Mediator {}
 
 impl Mediator {
+    // Leaves the idle state, restoring the previous vote system unless it got locked meanwhile.
+    fn wake_up(
+        idle: &Mutex<Idle>,
+        active_system: &ActiveVoteSystem,
+        system_lock: &Mutex<Option<VoteSystemLock>>,
+        tx_update: &MediatorUpdateSender,
+    ) {
+        let mut _idle = idle.lock().unwrap();
+        _idle.last_input = active_system.clock.now();
+
+        if _idle.status.take().is_none() {
+            return;
+        }
+
+        if let Some(system) = _idle.previous_system.take() {
+            active_system.swap_unless_locked(system, system_lock);
+        }
+
+        tx_update.send(MediatorUpdate::IdleChange(None)).unwrap();
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn spawn_input_reader<I>(
         command_input: I,
-        vote_lock: Arc<Mutex<VoteFunction>>,
+        active_system: ActiveVoteSystem,
         system_counter: VoteCounter<VoteSystem>,
+        system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
+        idle: Arc<Mutex<Idle>>,
+        paused: Arc<Mutex<Option<PauseStatus>>>,
+        mut modifier_votes: ModifierVotes,
+        mut filters: FilterPipeline,
         tx_update: MediatorUpdateSender,
     ) where
         I: CommandInput + 'static,
@@ -45,17 +346,71 @@ impl Mediator {
             let rx_input = command_input.create_receiver();
 
             loop {
-                let input = rx_input.recv().unwrap();
+                let result = filters.run(rx_input.recv().unwrap());
+
+                for user in filters.take_flagged() {
+                    println!("Mediator::input_reader: flagging {}", user);
+                    tx_update.send(MediatorUpdate::UserFlagged(user)).unwrap();
+                }
+                if let Some(counters) = filters.take_counters() {
+                    tx_update
+                        .send(MediatorUpdate::FilterCountersChange(counters))
+                        .unwrap();
+                }
+
+                let input = match result {
+                    Ok(input) => input,
+                    Err(dropped) => {
+                        tx_update
+                            .send(MediatorUpdate::InputDropped(dropped))
+                            .unwrap();
+                        continue;
+                    }
+                };
+
                 tx_update
                     .send(MediatorUpdate::Input(input.clone()))
                     .unwrap();
 
-                let Input(cmd, user) = input;
+                // Chat is still shown while paused, but nothing reaches the vote systems.
+                if paused.lock().unwrap().is_some() {
+                    continue;
+                }
 
-                if let Command::ChangeVoteSystem(system) = cmd {
-                    system_counter.vote(system);
-                } else {
-                    vote_lock.lock().unwrap().call(cmd);
+                Self::wake_up(&idle, &active_system, &system_lock, &tx_update);
+
+                match input.0 {
+                    Command::ChangeVoteSystem(system) => {
+                        // Mode votes are ignored while the vote system is locked.
+                        if system_lock.lock().unwrap().is_none() {
+                            system_counter.vote(system);
+                        }
+                    }
+                    Command::Modifier(modifier) => {
+                        if let Some(active) = modifier_votes.vote(modifier, &input.1.name) {
+                            println!("Mediator::input_reader: activating {:?}", modifier);
+                            tx_update
+                                .send(MediatorUpdate::ModifierActivated(active))
+                                .unwrap();
+                        }
+                    }
+                    _ => active_system.call(input),
+                }
+            }
+        });
+    }
+
+    fn spawn_connection_state_receiver(
+        rx_state: Receiver<ConnectionState>,
+        tx_mediator_update: MediatorUpdateSender,
+    ) {
+        thread::spawn(move || {
+            for state in rx_state.iter() {
+                if tx_mediator_update
+                    .send(MediatorUpdate::ConnectionChange(state))
+                    .is_err()
+                {
+                    break;
                 }
             }
         });
@@ -63,6 +418,7 @@ impl Mediator {
 
     fn spawn_vote_system_update_receiver(
         rx_vote_system_update: VoteSystemUpdateReceiver,
+        paused: Arc<Mutex<Option<PauseStatus>>>,
         tx_mediator_update: MediatorUpdateSender,
     ) {
         thread::spawn(move || {
@@ -72,15 +428,11 @@ impl Mediator {
 
             loop {
                 match rx_vote_system_update.recv() {
-                    Ok(update) => {
-                        let med_update = match update {
-                            VSU::Decision(cmd) => MU::Decision(Command(cmd)),
-                            VSU::DemocracyPartialResults(t, part) => {
-                                MU::VoteSystemDemocracyPartialResults(t, part)
-                            }
-                        };
-
-                        tx_mediator_update.send(med_update).unwrap();
+                    Ok(VSU::Decision(_)) if paused.lock().unwrap().is_some() => {
+                        println!("Mediator::vote_system_update: dropping decision, paused");
+                    }
+                    Ok(VSU::Decision(cmd)) => {
+                        tx_mediator_update.send(MU::Decision(Command(cmd))).unwrap();
                     }
                     Err(e) => {
                         println!("Mediator::vote_system_update: got {} err", e);
@@ -90,46 +442,378 @@ impl Mediator {
         });
     }
 
-    fn swap_vote_system(
-        current_system: &Arc<Mutex<VoteSystem>>,
-        vote_lock: &Arc<Mutex<VoteFunction>>,
-        tx_decision: VoteSystemUpdateSender,
-        new_vote_system: VoteSystem,
-    ) {
-        let vote_fn = new_vote_system.creator().create(tx_decision);
-
-        *vote_lock.lock().unwrap() = vote_fn;
-        *current_system.lock().unwrap() = new_vote_system;
-    }
-
     fn spawn_vote_system_changer(
-        current_system: Arc<Mutex<VoteSystem>>,
-        vote_lock: Arc<Mutex<VoteFunction>>,
-        tx_decision: VoteSystemUpdateSender,
+        active_system: ActiveVoteSystem,
         system_counter: VoteCounter<VoteSystem>,
+        system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
         last_vote_system_change: Arc<Mutex<Instant>>,
-        tx_mediator_update: MediatorUpdateSender,
     ) {
+        let clock = active_system.clock.clone();
+
         thread::spawn(move || loop {
-            thread::sleep(Duration::from_secs(30));
+            clock.sleep(Duration::from_secs(30));
 
             println!("spawn_vote_system_changer: running...");
-            *last_vote_system_change.lock().unwrap() = Instant::now();
+            *last_vote_system_change.lock().unwrap() = clock.now();
 
             let winner = system_counter.winner();
             system_counter.reset();
 
             if let Some(system) = winner {
-                if *current_system.lock().unwrap() != system {
-                    Self::swap_vote_system(
-                        &current_system,
-                        &vote_lock,
-                        tx_decision.clone(),
-                        system,
-                    );
+                active_system.swap_unless_locked(system, &system_lock);
+            }
+        });
+    }
+
+    // Forces the vote systems of the schedule entries, as they become active.
+    fn spawn_scheduler(
+        schedule: Vec<ScheduleEntry>,
+        active_system: ActiveVoteSystem,
+        system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
+        tx_mediator_update: MediatorUpdateSender,
+    ) {
+        if schedule.is_empty() {
+            return;
+        }
+        // Checked when the config was loaded.
+        let schedule: Vec<ScheduledEntry> = schedule
+            .into_iter()
+            .map(|entry| entry.scheduled().unwrap())
+            .collect();
+
+        let clock = active_system.clock.clone();
+        let session_start = clock.now();
+
+        thread::spawn(move || {
+            // The entry and start of the running segment, so the next run of the same entry
+            // counts as a new segment.
+            let mut active_segment = None;
+            // The vote system from before the segments, and the one they forced.
+            let mut restore: Option<(VoteSystem, VoteSystem)> = None;
+            // The lock the segments took, so one taken by hand meanwhile is left alone.
+            let mut own_lock: Option<VoteSystemLock> = None;
+
+            loop {
+                let now = clock.unix_now();
+                let active = schedule.iter().enumerate().find_map(|(i, entry)| {
+                    entry
+                        .active(clock.elapsed(session_start), now)
+                        .map(|segment| (i, segment))
+                });
+
+                if active.map(|(i, segment)| (i, segment.start)) != active_segment {
+                    active_segment = active.map(|(i, segment)| (i, segment.start));
+
+                    let forced = active.and_then(|(i, segment)| {
+                        let entry = &schedule[i].entry;
+
+                        match active_system.registry.get(&entry.vote_system) {
+                            Some(system) => Some((system, entry.lock, segment.ends_at)),
+                            None => {
+                                println!(
+                                    "Mediator::scheduler: unknown vote system {}",
+                                    entry.vote_system
+                                );
+                                None
+                            }
+                        }
+                    });
+
+                    // Held while swapping, so nothing else swaps in between.
+                    let mut _lock = system_lock.lock().unwrap();
+                    let own = own_lock.take();
+                    match forced {
+                        Some((system, lock, ends_at)) => {
+                            println!("Mediator::scheduler: forcing {:?}", system);
+                            let previous = match restore.take() {
+                                Some((previous, _)) => previous,
+                                None => active_system.current(),
+                            };
+                            restore = Some((previous, system.clone()));
+                            active_system.swap(system.clone());
+
+                            *_lock = if lock {
+                                Some(VoteSystemLock {
+                                    system,
+                                    ends_at: Some(ends_at),
+                                })
+                            } else {
+                                None
+                            };
+                            own_lock = _lock.clone();
+                        }
+                        None => {
+                            if _lock.is_some() && *_lock == own {
+                                *_lock = None;
+                            }
+
+                            // Unless it was changed or locked meanwhile, the forced system ends
+                            // with the segment.
+                            if let Some((previous, forced)) = restore.take() {
+                                if _lock.is_none() && active_system.current() == forced {
+                                    println!("Mediator::scheduler: restoring {:?}", previous);
+                                    active_system.swap(previous);
+                                }
+                            }
+                        }
+                    }
+
+                    tx_mediator_update
+                        .send(MediatorUpdate::VoteSystemLockChange(_lock.clone()))
+                        .unwrap();
+                }
+
+                clock.sleep(Duration::from_secs(1));
+            }
+        });
+    }
+
+    // Applies an operator request. Returns the request that reverts it, if it changed anything.
+    fn apply_control(
+        control: MediatorControl,
+        active_system: &ActiveVoteSystem,
+        system_lock: &Mutex<Option<VoteSystemLock>>,
+        paused: &Mutex<Option<PauseStatus>>,
+        user_list: &UserList,
+        tx_mediator_update: &MediatorUpdateSender,
+    ) -> Option<MediatorControl> {
+        use MediatorControl::*;
+
+        // Undoing a ban change restores whatever was left of the previous ban.
+        let remaining = |until: Option<u64>| {
+            let now = active_system.clock.unix_now();
+            until.map(|until| until.saturating_sub(now))
+        };
+
+        match control {
+            Pause(_) | Resume => {
+                let mut _paused = paused.lock().unwrap();
+                let reason = match control {
+                    Pause(reason) => Some(reason),
+                    _ => None,
+                };
+
+                if _paused.as_ref().map(|status| &status.reason) == reason.as_ref() {
+                    return None;
+                }
+
+                let undo = match *_paused {
+                    Some(ref status) => Pause(status.reason.clone()),
+                    None => Resume,
+                };
+                *_paused = reason.map(|reason| PauseStatus {
+                    reason,
+                    since: active_system.clock.unix_now(),
+                });
+
+                tx_mediator_update
+                    .send(MediatorUpdate::PauseChange(_paused.clone()))
+                    .unwrap();
+
+                Some(undo)
+            }
+            ChangeVoteSystem(system) => {
+                // Operators can change a locked vote system, which stays locked to the one picked.
+                let mut _lock = system_lock.lock().unwrap();
+                let previous = active_system.current();
+                if previous == system {
+                    return None;
+                }
+
+                active_system.swap(system.clone());
+
+                if let Some(ref mut lock) = *_lock {
+                    lock.system = system;
+                    tx_mediator_update
+                        .send(MediatorUpdate::VoteSystemLockChange(Some(lock.clone())))
+                        .unwrap();
+                }
+
+                Some(ChangeVoteSystem(previous))
+            }
+            Lock | Unlock | Relock(_) => {
+                let mut _lock = system_lock.lock().unwrap();
+                let lock = match control {
+                    Lock => Some(VoteSystemLock {
+                        system: active_system.current(),
+                        ends_at: None,
+                    }),
+                    // Unless it would have run out by now.
+                    Relock(lock) => Some(lock).filter(|lock| match lock.ends_at {
+                        Some(ends_at) => active_system.clock.unix_now() < ends_at,
+                        None => true,
+                    }),
+                    _ => None,
+                };
+                if _lock.is_some() == lock.is_some() {
+                    return None;
+                }
+
+                if let Some(ref lock) = lock {
+                    if active_system.current() != lock.system {
+                        active_system.swap(lock.system.clone());
+                    }
+                }
+                let undo = match _lock.take() {
+                    Some(previous) => Relock(previous),
+                    None => Unlock,
+                };
+                *_lock = lock;
+
+                tx_mediator_update
+                    .send(MediatorUpdate::VoteSystemLockChange(_lock.clone()))
+                    .unwrap();
+
+                Some(undo)
+            }
+            Ban(user, secs) => match user_list.ban(&user, secs) {
+                Some(until) => Some(Ban(user, remaining(until))),
+                None => Some(Unban(user)),
+            },
+            ChatBan(user, secs) => {
+                user_list.ban_from_chat(&user, secs);
+                None
+            }
+            Unban(user) => user_list
+                .unban(&user)
+                .map(|until| Ban(user, remaining(until))),
+            Ignore(user) => {
+                if user_list.ignore(&user) {
+                    Some(Unignore(user))
+                } else {
+                    None
+                }
+            }
+            Unignore(user) => {
+                if user_list.unignore(&user) {
+                    Some(Ignore(user))
+                } else {
+                    None
+                }
+            }
+            Undo => None,
+        }
+    }
+
+    fn spawn_control_receiver(
+        rx_control: MediatorControlReceiver,
+        active_system: ActiveVoteSystem,
+        system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
+        paused: Arc<Mutex<Option<PauseStatus>>>,
+        user_list: Arc<UserList>,
+        tx_mediator_update: MediatorUpdateSender,
+    ) {
+        thread::spawn(move || {
+            let apply = |control| {
+                Self::apply_control(
+                    control,
+                    &active_system,
+                    &system_lock,
+                    &paused,
+                    &user_list,
+                    &tx_mediator_update,
+                )
+            };
+            let mut undo_stack = VecDeque::new();
+
+            // Stops once every operator is gone.
+            for control in rx_control.iter() {
+                println!("Mediator::control_receiver: got {:?}", control);
+
+                if let MediatorControl::Undo = control {
+                    if let Some(undo) = undo_stack.pop_back() {
+                        apply(undo);
+                    }
+                } else if let Some(undo) = apply(control) {
+                    undo_stack.push_back(undo);
+                    // Only the latest requests can be undone.
+                    if undo_stack.len() > UNDO_LIMIT {
+                        undo_stack.pop_front();
+                    }
+                }
+            }
+        });
+    }
+
+    // Applies the idle action once chat has been silent for long enough.
+    fn spawn_idle_watcher(
+        config: IdleConfig,
+        active_system: ActiveVoteSystem,
+        system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
+        idle: Arc<Mutex<Idle>>,
+        paused: Arc<Mutex<Option<PauseStatus>>>,
+        tx_mediator_update: MediatorUpdateSender,
+    ) {
+        let action = match config.action {
+            Some(ref action) => action.clone(),
+            None => return,
+        };
+        let buttons = config.buttons();
+        let clock = active_system.clock.clone();
+
+        thread::spawn(move || {
+            let mut presses = 0;
+            let mut last_press: Option<Instant> = None;
+
+            loop {
+                clock.sleep(Duration::from_secs(1));
+
+                let mut _idle = idle.lock().unwrap();
+
+                // Chat isn't expected to play while paused.
+                if paused.lock().unwrap().is_some() {
+                    _idle.last_input = clock.now();
+                    continue;
+                }
+
+                if _idle.status.is_none() {
+                    if clock.elapsed(_idle.last_input) < Duration::from_secs(config.after_secs) {
+                        continue;
+                    }
+
+                    println!("Mediator::idle_watcher: chat is idle, running {:?}", action);
+                    let status = IdleStatus {
+                        since: clock.unix_now(),
+                        action: action.clone(),
+                    };
+                    _idle.status = Some(status.clone());
+
+                    if let IdleAction::VoteSystem(ref name) = action {
+                        match active_system.registry.get(name) {
+                            Some(system) => {
+                                if system_lock.lock().unwrap().is_none() {
+                                    _idle.previous_system = Some(active_system.current());
+                                    active_system.swap(system);
+                                }
+                            }
+                            None => {
+                                println!("Mediator::idle_watcher: unknown vote system {}", name)
+                            }
+                        }
+                    }
+
+                    presses = 0;
+                    last_press = None;
+
+                    tx_mediator_update
+                        .send(MediatorUpdate::IdleChange(Some(status)))
+                        .unwrap();
+                }
+
+                let due = match last_press {
+                    Some(t) => clock.elapsed(t) >= Duration::from_secs(config.interval_secs),
+                    None => true,
+                };
+
+                if !buttons.is_empty() && due {
+                    let button = buttons[presses % buttons.len()];
+                    presses += 1;
+                    last_press = Some(clock.now());
 
                     tx_mediator_update
-                        .send(MediatorUpdate::VoteSystemChange(system))
+                        .send(MediatorUpdate::Decision(MediatedDecision::Command(
+                            Command::Action(button),
+                        )))
                         .unwrap();
                 }
             }
@@ -137,16 +821,18 @@ impl Mediator {
     }
 
     fn spawn_vote_system_time_updater(
+        active_system: ActiveVoteSystem,
         last_vote_system_change: Arc<Mutex<Instant>>,
         tx_mediator_update: MediatorUpdateSender,
     ) {
+        let clock = active_system.clock.clone();
+
         thread::spawn(move || loop {
-            thread::sleep(Duration::from_secs(1));
+            clock.sleep(Duration::from_secs(1));
 
             // Sometimes elapsed() can be >30s.
-            let time_remaining =
-                Duration::from_secs(30)
-                .checked_sub(last_vote_system_change.lock().unwrap().elapsed())
+            let time_remaining = Duration::from_secs(30)
+                .checked_sub(clock.elapsed(*last_vote_system_change.lock().unwrap()))
                 .unwrap_or(Duration::from_secs(0));
 
             tx_mediator_update
@@ -154,46 +840,115 @@ impl Mediator {
                     time_remaining.as_secs(),
                 ))
                 .unwrap();
+
+            tx_mediator_update
+                .send(MediatorUpdate::VoteSystemSnapshot(active_system.tick()))
+                .unwrap();
         });
     }
 
-    pub fn create<I>(command_input: I, system: VoteSystem) -> MediatorUpdateReceiver
+    pub fn create<I>(
+        command_input: I,
+        system: VoteSystem,
+        registry: Arc<VoteSystemRegistry>,
+        config: MediatorConfig,
+        rx_control: MediatorControlReceiver,
+        user_list: Arc<UserList>,
+        clock: SharedClock,
+    ) -> MediatorUpdateReceiver
     where
         I: CommandInput + 'static,
     {
         let (tx_decision, rx_vote_system_update) = channel();
-        let vote_function = system.creator().create(tx_decision.clone());
+        let vote_function = registry
+            .create(&system, tx_decision.clone(), clock.clone())
+            .unwrap_or_else(|| panic!("Mediator::create: {:?} is not registered", system));
+        vote_function.on_activate(Vec::new());
         let (tx_mediator_update, rx_mediator_update) = channel();
 
-        let vote_lock = Arc::new(Mutex::new(vote_function));
-        let last_vote_system_change = Arc::new(Mutex::new(Instant::now()));
+        let last_vote_system_change = Arc::new(Mutex::new(clock.now()));
 
         // Send initial VoteSystem update
         tx_mediator_update
-            .send(MediatorUpdate::VoteSystemChange(system))
+            .send(MediatorUpdate::VoteSystemChange(system.clone()))
             .unwrap();
 
-        // Use Democracy as a reference element when calculating vote percentage, as
-        // 0 means full Anarchy and 1, full Democracy. Helps when drawing a progress bar.
-        let vote_counter = VoteCounter::new(tx_mediator_update.clone(), VoteSystem::Democracy);
-        let current_system = Arc::new(Mutex::new(system));
+        let vote_counter = VoteCounter::new(
+            tx_mediator_update.clone(),
+            registry.systems(),
+            MediatorUpdate::VoteSystemVotesChange,
+        );
+        // Sends the initial, empty, breakdown.
+        vote_counter.reset();
+
+        let active_system = ActiveVoteSystem {
+            running: Arc::new(Mutex::new(Running {
+                system,
+                vote_fn: vote_function,
+            })),
+            tx_decision,
+            registry,
+            clock: clock.clone(),
+            tx_mediator_update: tx_mediator_update.clone(),
+        };
+        let system_lock = Arc::new(Mutex::new(None));
+        let idle = Arc::new(Mutex::new(Idle {
+            last_input: clock.now(),
+            status: None,
+            previous_system: None,
+        }));
+        let paused = Arc::new(Mutex::new(None));
+        let filters = FilterPipeline::new(&config.filters(), &user_list, &clock);
 
+        if let Some(rx_state) = command_input.connection_states() {
+            Self::spawn_connection_state_receiver(rx_state, tx_mediator_update.clone());
+        }
         Self::spawn_input_reader(
             command_input,
-            vote_lock.clone(),
+            active_system.clone(),
             vote_counter.clone(),
+            system_lock.clone(),
+            idle.clone(),
+            paused.clone(),
+            ModifierVotes::new(config.modifiers, clock.clone()),
+            filters,
+            tx_mediator_update.clone(),
+        );
+        Self::spawn_vote_system_update_receiver(
+            rx_vote_system_update,
+            paused.clone(),
+            tx_mediator_update.clone(),
+        );
+        Self::spawn_control_receiver(
+            rx_control,
+            active_system.clone(),
+            system_lock.clone(),
+            paused.clone(),
+            user_list,
             tx_mediator_update.clone(),
         );
-        Self::spawn_vote_system_update_receiver(rx_vote_system_update, tx_mediator_update.clone());
         Self::spawn_vote_system_changer(
-            current_system.clone(),
-            vote_lock.clone(),
-            tx_decision.clone(),
+            active_system.clone(),
             vote_counter.clone(),
+            system_lock.clone(),
             last_vote_system_change.clone(),
+        );
+        Self::spawn_scheduler(
+            config.schedule,
+            active_system.clone(),
+            system_lock.clone(),
+            tx_mediator_update.clone(),
+        );
+        Self::spawn_idle_watcher(
+            config.idle,
+            active_system.clone(),
+            system_lock,
+            idle,
+            paused,
             tx_mediator_update.clone(),
         );
         Self::spawn_vote_system_time_updater(
+            active_system,
             last_vote_system_change.clone(),
             tx_mediator_update.clone(),
         );
@@ -201,3 +956,518 @@ impl Mediator {
         rx_mediator_update
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::command_input::Chatter;
+    use crate::modifier::Modifier;
+    use crate::user_list::UserListConfig;
+
+    struct TestInput(Mutex<Option<Receiver<Input>>>);
+
+    impl CommandInput for TestInput {
+        fn create_receiver(&self) -> Receiver<Input> {
+            self.0.lock().unwrap().take().unwrap()
+        }
+    }
+
+    fn mediator(
+        config: MediatorConfig,
+        clock: &Arc<ManualClock>,
+    ) -> (Sender<Input>, MediatorControlSender, MediatorUpdateReceiver) {
+        let user_list = UserList::load(&UserListConfig { path: None }, clock.clone());
+
+        mediator_with_users(config, clock, Arc::new(user_list))
+    }
+
+    fn mediator_with_users(
+        config: MediatorConfig,
+        clock: &Arc<ManualClock>,
+        user_list: Arc<UserList>,
+    ) -> (Sender<Input>, MediatorControlSender, MediatorUpdateReceiver) {
+        let (tx_input, rx_input) = channel();
+        let (tx_control, rx_control) = channel();
+        let registry = Arc::new(VoteSystemRegistry::with_builtin_systems(&Default::default()));
+
+        let rx_update = Mediator::create(
+            TestInput(Mutex::new(Some(rx_input))),
+            VoteSystem::new("Anarchy"),
+            registry,
+            config,
+            rx_control,
+            user_list,
+            clock.clone(),
+        );
+
+        (tx_input, tx_control, rx_update)
+    }
+
+    fn vote_system_changes(rx: &MediatorUpdateReceiver) -> Vec<VoteSystem> {
+        rx.try_iter()
+            .filter_map(|update| match update {
+                MediatorUpdate::VoteSystemChange(system) => Some(system),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Waits until the input reader has counted a mode vote.
+    fn wait_for_mode_vote(rx: &MediatorUpdateReceiver) {
+        loop {
+            if let MediatorUpdate::VoteSystemVotesChange(votes) = rx.recv().unwrap() {
+                if votes.iter().any(|&(_, count)| count > 0) {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn switches_to_most_voted_system_every_30_secs() {
+        let clock = Arc::new(ManualClock::new());
+        // The vote system changer and the time updater, as anarchy has no threads.
+        let threads = 2;
+        let (tx_input, _tx_control, rx) = mediator(MediatorConfig::default(), &clock);
+        clock.wait_for_sleepers(threads);
+
+        assert_eq!(vote_system_changes(&rx), vec![VoteSystem::new("Anarchy")]);
+
+        let democracy = VoteSystem::new("Democracy");
+        tx_input
+            .send(Input(
+                Command::ChangeVoteSystem(democracy.clone()),
+                Chatter::new("user"),
+            ))
+            .unwrap();
+        wait_for_mode_vote(&rx);
+
+        clock.advance(Duration::from_secs(29));
+        clock.wait_for_sleepers(threads);
+        assert_eq!(vote_system_changes(&rx), vec![]);
+
+        clock.advance(Duration::from_secs(1));
+        // Democracy adds its round counter.
+        clock.wait_for_sleepers(threads + 1);
+        assert_eq!(vote_system_changes(&rx), vec![democracy]);
+    }
+
+    #[test]
+    fn schedule_forces_and_locks_vote_system() {
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            schedule: vec![ScheduleEntry {
+                vote_system: "Democracy".to_owned(),
+                daily_at: None,
+                cron: None,
+                after_mins: Some(1),
+                every_mins: None,
+                duration_mins: 5,
+                lock: true,
+            }],
+            ..MediatorConfig::default()
+        };
+        // The scheduler runs alongside the changer and the time updater.
+        let threads = 3;
+        let (_tx_input, tx_control, rx) = mediator(config, &clock);
+        clock.wait_for_sleepers(threads);
+
+        clock.advance(Duration::from_secs(60));
+        clock.wait_for_sleepers(threads + 1);
+
+        let mut changes = Vec::new();
+        let mut locks = Vec::new();
+        for update in rx.try_iter() {
+            match update {
+                MediatorUpdate::VoteSystemChange(system) => changes.push(system),
+                MediatorUpdate::VoteSystemLockChange(lock) => locks.push(lock),
+                _ => (),
+            }
+        }
+
+        let democracy = VoteSystem::new("Democracy");
+        assert_eq!(changes, vec![VoteSystem::new("Anarchy"), democracy.clone()]);
+        let lock = VoteSystemLock {
+            system: democracy,
+            ends_at: Some(6 * 60),
+        };
+        assert_eq!(locks, vec![Some(lock.clone())]);
+
+        // Undoing an unlock puts the segment's lock back as it was.
+        tx_control.send(MediatorControl::Unlock).unwrap();
+        tx_control.send(MediatorControl::Undo).unwrap();
+        let mut locks = Vec::new();
+        while locks.len() < 2 {
+            if let MediatorUpdate::VoteSystemLockChange(lock) = rx.recv().unwrap() {
+                locks.push(lock);
+            }
+        }
+        assert_eq!(locks, vec![None, Some(lock)]);
+
+        // Once the segment ends, whatever ran before it is back.
+        clock.advance(Duration::from_secs(5 * 60));
+        let mut changes = Vec::new();
+        loop {
+            match rx.recv().unwrap() {
+                MediatorUpdate::VoteSystemChange(system) => changes.push(system),
+                MediatorUpdate::VoteSystemLockChange(lock) => {
+                    assert_eq!(lock, None);
+                    break;
+                }
+                _ => (),
+            }
+        }
+        assert_eq!(changes, vec![VoteSystem::new("Anarchy")]);
+    }
+
+    fn next_lock(rx: &MediatorUpdateReceiver) -> Option<VoteSystemLock> {
+        loop {
+            if let MediatorUpdate::VoteSystemLockChange(lock) = rx.recv().unwrap() {
+                return lock;
+            }
+        }
+    }
+
+    #[test]
+    fn schedule_renews_the_lock_for_each_run() {
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            schedule: vec![ScheduleEntry {
+                vote_system: "Democracy".to_owned(),
+                daily_at: None,
+                cron: Some("*/5 * * * *".to_owned()),
+                after_mins: None,
+                every_mins: None,
+                duration_mins: 5,
+                lock: true,
+            }],
+            ..MediatorConfig::default()
+        };
+        let threads = 3;
+        let (_tx_input, _tx_control, rx) = mediator(config, &clock);
+
+        assert_eq!(next_lock(&rx).unwrap().ends_at, Some(5 * 60));
+        clock.wait_for_sleepers(threads);
+        clock.advance(Duration::from_secs(5 * 60));
+        assert_eq!(next_lock(&rx).unwrap().ends_at, Some(10 * 60));
+    }
+
+    #[test]
+    fn schedule_keeps_locks_taken_during_a_segment() {
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            schedule: vec![ScheduleEntry {
+                vote_system: "Democracy".to_owned(),
+                daily_at: None,
+                cron: None,
+                after_mins: Some(1),
+                every_mins: None,
+                duration_mins: 5,
+                lock: true,
+            }],
+            ..MediatorConfig::default()
+        };
+        let threads = 3;
+        let (_tx_input, tx_control, rx) = mediator(config, &clock);
+        clock.wait_for_sleepers(threads);
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(next_lock(&rx).unwrap().ends_at, Some(6 * 60));
+
+        tx_control.send(MediatorControl::Unlock).unwrap();
+        tx_control.send(MediatorControl::Lock).unwrap();
+        assert_eq!(next_lock(&rx), None);
+        let lock = next_lock(&rx).unwrap();
+        assert_eq!(lock.ends_at, None);
+
+        clock.wait_for_sleepers(threads);
+        clock.advance(Duration::from_secs(5 * 60));
+        let mut changes = Vec::new();
+        loop {
+            match rx.recv().unwrap() {
+                MediatorUpdate::VoteSystemChange(system) => changes.push(system),
+                MediatorUpdate::VoteSystemLockChange(after) => {
+                    assert_eq!(after, Some(lock));
+                    break;
+                }
+                _ => (),
+            }
+        }
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn activates_modifier_once_enough_chatters_vote() {
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            modifiers: ModifiersConfig {
+                votes_needed: 2,
+                ..ModifiersConfig::default()
+            },
+            ..MediatorConfig::default()
+        };
+        let (tx_input, _tx_control, rx) = mediator(config, &clock);
+
+        for user in &["first", "second", "third"] {
+            tx_input
+                .send(Input(
+                    Command::Modifier(Modifier::Invert),
+                    Chatter::new(user),
+                ))
+                .unwrap();
+        }
+        // Anything after the votes shows they were all handled.
+        tx_input
+            .send(Input(Command::Action(Button::A), Chatter::new("first")))
+            .unwrap();
+
+        let mut activated = Vec::new();
+        loop {
+            match rx.recv().unwrap() {
+                MediatorUpdate::ModifierActivated(active) => activated.push(active),
+                MediatorUpdate::Input(Input(Command::Action(_), _)) => break,
+                _ => (),
+            }
+        }
+
+        assert_eq!(
+            activated,
+            vec![ActiveModifier {
+                modifier: Modifier::Invert,
+                ends_at: 60,
+            }]
+        );
+    }
+
+    #[test]
+    fn stops_forwarding_inputs_while_paused() {
+        let clock = Arc::new(ManualClock::new());
+        let (tx_input, tx_control, rx) = mediator(MediatorConfig::default(), &clock);
+
+        tx_control
+            .send(MediatorControl::Pause(Some("brb".to_owned())))
+            .unwrap();
+        loop {
+            if let MediatorUpdate::PauseChange(status) = rx.recv().unwrap() {
+                assert_eq!(status.unwrap().reason, Some("brb".to_owned()));
+                break;
+            }
+        }
+
+        tx_input
+            .send(Input(Command::Action(Button::A), Chatter::new("user")))
+            .unwrap();
+        // Once the next input is read, A was already dropped. The sentinel itself may not be.
+        tx_input
+            .send(Input(Command::Action(Button::Start), Chatter::new("user")))
+            .unwrap();
+        loop {
+            if let MediatorUpdate::Input(Input(Command::Action(Button::Start), _)) =
+                rx.recv().unwrap()
+            {
+                break;
+            }
+        }
+
+        tx_control.send(MediatorControl::Resume).unwrap();
+        loop {
+            if let MediatorUpdate::PauseChange(status) = rx.recv().unwrap() {
+                assert_eq!(status, None);
+                break;
+            }
+        }
+
+        tx_input
+            .send(Input(Command::Action(Button::B), Chatter::new("user")))
+            .unwrap();
+        loop {
+            if let MediatorUpdate::Decision(MediatedDecision::Command(command)) = rx.recv().unwrap()
+            {
+                assert_ne!(command, Command::Action(Button::A));
+
+                if command == Command::Action(Button::B) {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn undoes_moderator_commands_in_reverse() {
+        let clock = Arc::new(ManualClock::new());
+        let user_list = Arc::new(UserList::load(
+            &UserListConfig { path: None },
+            clock.clone(),
+        ));
+        let (_tx_input, tx_control, rx) =
+            mediator_with_users(MediatorConfig::default(), &clock, user_list.clone());
+        let democracy = VoteSystem::new("Democracy");
+
+        tx_control
+            .send(MediatorControl::ChangeVoteSystem(democracy.clone()))
+            .unwrap();
+        tx_control
+            .send(MediatorControl::Ban("troll".to_owned(), Some(600)))
+            .unwrap();
+        tx_control
+            .send(MediatorControl::ChatBan("twitch:spammer".to_owned(), None))
+            .unwrap();
+        tx_control.send(MediatorControl::Lock).unwrap();
+        loop {
+            if let MediatorUpdate::VoteSystemLockChange(lock) = rx.recv().unwrap() {
+                assert_eq!(lock.unwrap().system, democracy);
+                break;
+            }
+        }
+
+        assert!(!user_list.allows("Troll"));
+        assert!(user_list.allows("user"));
+
+        for _ in 0..3 {
+            tx_control.send(MediatorControl::Undo).unwrap();
+        }
+
+        let mut locks = Vec::new();
+        loop {
+            match rx.recv().unwrap() {
+                MediatorUpdate::VoteSystemLockChange(lock) => locks.push(lock),
+                MediatorUpdate::VoteSystemChange(system) => {
+                    assert_eq!(system, VoteSystem::new("Anarchy"));
+                    break;
+                }
+                _ => (),
+            }
+        }
+        assert_eq!(locks, vec![None]);
+        assert!(user_list.allows("Troll"));
+        assert!(!user_list.allows("twitch:spammer"));
+    }
+
+    #[test]
+    fn moves_old_spam_settings_to_the_spam_filter() {
+        let config: MediatorConfig =
+            serde_json::from_str(r#"{"spam": {"max_repeats": 5}}"#).unwrap();
+
+        match config.filters().as_slice() {
+            [FilterConfig::Users, FilterConfig::Spam(spam)] => {
+                assert_eq!(spam.max_inputs, Some(3));
+                assert_eq!(spam.max_repeats, 5);
+            }
+            filters => panic!("unexpected filters {:?}", filters),
+        }
+    }
+
+    #[test]
+    fn drops_spam_and_flags_spammers() {
+        use crate::input_filter::{DropReason, SpamFilterConfig};
+
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            filters: vec![FilterConfig::Spam(SpamFilterConfig {
+                max_inputs: Some(3),
+                window_ms: 1000,
+                max_repeats: 2,
+                flag_after: 2,
+            })],
+            ..MediatorConfig::default()
+        };
+        let (tx_input, _tx_control, rx) = mediator(config, &clock);
+
+        for &button in &[Button::A, Button::A, Button::A, Button::B, Button::B] {
+            tx_input
+                .send(Input(Command::Action(button), Chatter::new("spammer")))
+                .unwrap();
+        }
+        tx_input
+            .send(Input(Command::Action(Button::A), Chatter::new("user")))
+            .unwrap();
+
+        let mut events = Vec::new();
+        loop {
+            match rx.recv().unwrap() {
+                MediatorUpdate::Input(Input(Command::Action(button), user)) => {
+                    if user.name == "user" {
+                        break;
+                    }
+                    events.push(format!("{:?}", button));
+                }
+                MediatorUpdate::InputDropped(dropped) => {
+                    events.push(format!("{:?} {:?}", dropped.input.0, dropped.reason));
+                }
+                MediatorUpdate::UserFlagged(user) => events.push(user),
+                _ => (),
+            }
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                "A".to_owned(),
+                "A".to_owned(),
+                format!("Action(A) {:?}", DropReason::Repeated),
+                "B".to_owned(),
+                "spammer".to_owned(),
+                format!("Action(B) {:?}", DropReason::RateLimited),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_pressing_button_while_idle() {
+        let clock = Arc::new(ManualClock::new());
+        let config = MediatorConfig {
+            idle: IdleConfig {
+                after_secs: 60,
+                interval_secs: 5,
+                action: Some(IdleAction::KeepAlive(Button::A)),
+            },
+            ..MediatorConfig::default()
+        };
+        // The idle watcher runs alongside the changer and the time updater.
+        let threads = 3;
+        let (tx_input, _tx_control, rx) = mediator(config, &clock);
+        clock.wait_for_sleepers(threads);
+
+        let idle_changes = |rx: &MediatorUpdateReceiver| {
+            let mut changes = Vec::new();
+            let mut presses = 0;
+
+            for update in rx.try_iter() {
+                match update {
+                    MediatorUpdate::IdleChange(status) => changes.push(status.is_some()),
+                    MediatorUpdate::Decision(MediatedDecision::Command(Command::Action(
+                        Button::A,
+                    ))) => presses += 1,
+                    _ => (),
+                }
+            }
+
+            (changes, presses)
+        };
+
+        clock.advance(Duration::from_secs(59));
+        clock.wait_for_sleepers(threads);
+        assert_eq!(idle_changes(&rx), (vec![], 0));
+
+        clock.advance(Duration::from_secs(1));
+        clock.wait_for_sleepers(threads);
+        assert_eq!(idle_changes(&rx), (vec![true], 1));
+
+        for _ in 0..5 {
+            clock.advance(Duration::from_secs(1));
+            clock.wait_for_sleepers(threads);
+        }
+        assert_eq!(idle_changes(&rx), (vec![], 1));
+
+        tx_input
+            .send(Input(Command::Action(Button::B), Chatter::new("user")))
+            .unwrap();
+        loop {
+            if let MediatorUpdate::IdleChange(status) = rx.recv().unwrap() {
+                assert_eq!(status, None);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::command_input::Chatter;
+use crate::mediator::MediatorControl;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Who's allowed to send control commands through chat. Roles are ordered, so a
+// broadcaster can do anything a moderator can.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Moderator,
+    Broadcaster,
+}
+
+impl Role {
+    // From what the chat service told about the chatter, like their Twitch badges.
+    pub fn of(chatter: &Chatter) -> Self {
+        if chatter.badges.iter().any(|badge| badge == "broadcaster") {
+            Role::Broadcaster
+        } else if chatter.moderator {
+            Role::Moderator
+        } else {
+            Role::Viewer
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ModerationConfig {
+    // Accounts that are treated as moderators, even without the badge. A nick alone
+    // isn't enough, since anyone can take one on most IRC servers.
+    pub operators: Vec<String>,
+    // The role needed for each command, by name. Unlisted commands need a moderator.
+    pub permissions: HashMap<String, Role>,
+}
+
+impl ModerationConfig {
+    pub fn role_of(&self, chatter: &Chatter) -> Role {
+        let role = Role::of(chatter);
+
+        if role < Role::Moderator
+            && self
+                .operators
+                .iter()
+                .any(|op| matches!(chatter.account, Some(ref account) if op.eq_ignore_ascii_case(account)))
+        {
+            Role::Moderator
+        } else {
+            role
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self
+            .permissions
+            .keys()
+            .find(|name| !MediatorControl::NAMES.contains(&name.as_str()))
+        {
+            Some(name) => Err(format!("moderation: no command is called {}", name)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn allows(&self, role: Role, control: &MediatorControl) -> bool {
+        let needed = self
+            .permissions
+            .get(control.name())
+            .cloned()
+            .unwrap_or(Role::Moderator);
+
+        role >= needed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_roles_and_refuses_unknown_commands() {
+        let config = ModerationConfig {
+            operators: vec!["Helper".to_owned()],
+            permissions: vec![("ban".to_owned(), Role::Broadcaster)]
+                .into_iter()
+                .collect(),
+        };
+        let streamer = Chatter {
+            badges: vec!["broadcaster".to_owned()],
+            ..Chatter::new("streamer")
+        };
+        let moderator = Chatter {
+            moderator: true,
+            ..Chatter::new("moderator")
+        };
+
+        let helper = Chatter {
+            account: Some("helper".to_owned()),
+            ..Chatter::new("helper")
+        };
+
+        assert_eq!(config.role_of(&streamer), Role::Broadcaster);
+        assert_eq!(config.role_of(&moderator), Role::Moderator);
+        assert_eq!(config.role_of(&helper), Role::Moderator);
+        assert_eq!(config.role_of(&Chatter::new("helper")), Role::Viewer);
+        assert_eq!(config.role_of(&Chatter::new("viewer")), Role::Viewer);
+        assert!(config.validate().is_ok());
+
+        let config = ModerationConfig {
+            permissions: vec![("bna".to_owned(), Role::Viewer)].into_iter().collect(),
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+}
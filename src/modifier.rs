@@ -0,0 +1,327 @@
+use crate::clock::SharedClock;
+use crate::command::Button;
+use crate::command_input::User;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Timed twists that chat can vote into effect.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Modifier {
+    // Swaps up with down, and left with right.
+    Invert,
+    // Swaps A with B.
+    Mirror,
+    // Holds every input back for a while.
+    Delay,
+    // Every now and then, presses a random button instead.
+    Random,
+}
+
+impl Modifier {
+    pub fn from_string(s: &str) -> Option<Self> {
+        use Modifier::*;
+
+        match s {
+            "invert" => Some(Invert),
+            "mirror" => Some(Mirror),
+            "delay" => Some(Delay),
+            "random" => Some(Random),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ModifiersConfig {
+    // Votes needed within the window to turn a modifier on.
+    pub votes_needed: usize,
+    pub window_secs: u64,
+    pub duration_secs: u64,
+    pub delay_ms: u64,
+    pub random_chance: f64,
+}
+
+impl Default for ModifiersConfig {
+    fn default() -> Self {
+        ModifiersConfig {
+            votes_needed: 10,
+            window_secs: 60,
+            duration_secs: 60,
+            delay_ms: 2000,
+            random_chance: 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ActiveModifier {
+    pub modifier: Modifier,
+    // Unix timestamp, in seconds.
+    pub ends_at: u64,
+}
+
+// Counts the chatters voting for each modifier, over a sliding window.
+pub struct ModifierVotes {
+    config: ModifiersConfig,
+    clock: SharedClock,
+    // When each chatter last voted for it.
+    votes: HashMap<Modifier, HashMap<User, Instant>>,
+    active_until: HashMap<Modifier, Instant>,
+}
+
+impl ModifierVotes {
+    pub fn new(config: ModifiersConfig, clock: SharedClock) -> Self {
+        ModifierVotes {
+            config,
+            clock,
+            votes: HashMap::new(),
+            active_until: HashMap::new(),
+        }
+    }
+
+    // Returns the modifier once enough chatters voted for it. Votes for active modifiers are
+    // ignored.
+    pub fn vote(&mut self, modifier: Modifier, user: &User) -> Option<ActiveModifier> {
+        let now = self.clock.now();
+
+        if let Some(&until) = self.active_until.get(&modifier) {
+            if now < until {
+                return None;
+            }
+        }
+
+        let window = Duration::from_secs(self.config.window_secs);
+        let votes = self.votes.entry(modifier).or_default();
+        votes.insert(user.clone(), now);
+        votes.retain(|_, &mut at| now.duration_since(at) < window);
+
+        if votes.len() < self.config.votes_needed {
+            return None;
+        }
+
+        votes.clear();
+        self.active_until.insert(
+            modifier,
+            now + Duration::from_secs(self.config.duration_secs),
+        );
+
+        Some(ActiveModifier {
+            modifier,
+            ends_at: self.clock.unix_now() + self.config.duration_secs,
+        })
+    }
+}
+
+// The active modifiers, applied to decisions on their way to the output.
+pub struct Modifiers {
+    config: ModifiersConfig,
+    clock: SharedClock,
+    active: Vec<ActiveModifier>,
+    delayed: VecDeque<(Instant, Button)>,
+}
+
+impl Modifiers {
+    pub fn new(config: ModifiersConfig, clock: SharedClock) -> Self {
+        Modifiers {
+            config,
+            clock,
+            active: Vec::new(),
+            delayed: VecDeque::new(),
+        }
+    }
+
+    pub fn activate(&mut self, active: ActiveModifier) {
+        self.active.retain(|a| a.modifier != active.modifier);
+        self.active.push(active);
+    }
+
+    // Drops the modifiers that ran out, returns whether there were any.
+    pub fn expire(&mut self) -> bool {
+        let now = self.clock.unix_now();
+        let count = self.active.len();
+        self.active.retain(|a| now < a.ends_at);
+
+        self.active.len() != count
+    }
+
+    pub fn active(&self) -> Vec<ActiveModifier> {
+        self.active.clone()
+    }
+
+    fn is_active(&self, modifier: Modifier) -> bool {
+        self.active.iter().any(|a| a.modifier == modifier)
+    }
+
+    // Returns the button to press right away, unless it's delayed.
+    pub fn apply(&mut self, button: Button) -> Option<Button> {
+        use Button::*;
+
+        let mut button = button;
+
+        if self.is_active(Modifier::Random) {
+            let mut rng = rand::thread_rng();
+            let chance = self.config.random_chance.clamp(0.0, 1.0);
+
+            if rng.gen_bool(chance) {
+                button = *Button::ALL.choose(&mut rng).unwrap();
+            }
+        }
+
+        if self.is_active(Modifier::Invert) {
+            button = match button {
+                Up => Down,
+                Down => Up,
+                Left => Right,
+                Right => Left,
+                other => other,
+            };
+        }
+
+        if self.is_active(Modifier::Mirror) {
+            button = match button {
+                A => B,
+                B => A,
+                other => other,
+            };
+        }
+
+        if self.is_active(Modifier::Delay) {
+            let due = self.clock.now() + Duration::from_millis(self.config.delay_ms);
+            self.delayed.push_back((due, button));
+
+            return None;
+        }
+
+        Some(button)
+    }
+
+    // How long until the next delayed button is due, if there's any.
+    pub fn next_delayed(&self) -> Option<Duration> {
+        self.delayed.front().map(|&(due, _)| {
+            due.checked_duration_since(self.clock.now())
+                .unwrap_or(Duration::from_secs(0))
+        })
+    }
+
+    pub fn take_delayed(&mut self) -> Vec<Button> {
+        let now = self.clock.now();
+        let mut due = Vec::new();
+
+        while let Some(&(at, button)) = self.delayed.front() {
+            if at > now {
+                break;
+            }
+
+            due.push(button);
+            self.delayed.pop_front();
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+
+    fn config() -> ModifiersConfig {
+        ModifiersConfig {
+            votes_needed: 3,
+            window_secs: 10,
+            duration_secs: 60,
+            delay_ms: 500,
+            random_chance: 1.0,
+        }
+    }
+
+    fn active(modifier: Modifier) -> ActiveModifier {
+        ActiveModifier {
+            modifier,
+            ends_at: 60,
+        }
+    }
+
+    #[test]
+    fn counts_each_chatter_once_within_the_window() {
+        let clock = Arc::new(ManualClock::new());
+        let mut votes = ModifierVotes::new(config(), clock.clone());
+        let vote =
+            |votes: &mut ModifierVotes, user: &str| votes.vote(Modifier::Invert, &user.to_owned());
+
+        // One chatter repeating the vote is still one vote.
+        for _ in 0..5 {
+            assert_eq!(vote(&mut votes, "alice"), None);
+        }
+        assert_eq!(vote(&mut votes, "bob"), None);
+
+        // Their votes are too old by now, so they have to vote again.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(vote(&mut votes, "carol"), None);
+        assert_eq!(vote(&mut votes, "bob"), None);
+        assert_eq!(
+            vote(&mut votes, "alice"),
+            Some(ActiveModifier {
+                modifier: Modifier::Invert,
+                ends_at: 70,
+            })
+        );
+
+        // Votes for an active modifier don't count towards the next time.
+        for user in &["alice", "bob", "carol"] {
+            assert_eq!(vote(&mut votes, user), None);
+        }
+    }
+
+    #[test]
+    fn swaps_buttons_while_active() {
+        let clock = Arc::new(ManualClock::new());
+        let mut modifiers = Modifiers::new(config(), clock.clone());
+
+        assert_eq!(modifiers.apply(Button::Up), Some(Button::Up));
+
+        modifiers.activate(active(Modifier::Invert));
+        modifiers.activate(active(Modifier::Mirror));
+        assert_eq!(modifiers.apply(Button::Up), Some(Button::Down));
+        assert_eq!(modifiers.apply(Button::Left), Some(Button::Right));
+        assert_eq!(modifiers.apply(Button::A), Some(Button::B));
+        assert_eq!(modifiers.apply(Button::Start), Some(Button::Start));
+
+        clock.advance(Duration::from_secs(60));
+        assert!(modifiers.expire());
+        assert_eq!(modifiers.active(), vec![]);
+        assert_eq!(modifiers.apply(Button::Up), Some(Button::Up));
+    }
+
+    #[test]
+    fn delays_and_randomizes_buttons_while_active() {
+        let clock = Arc::new(ManualClock::new());
+        let mut modifiers = Modifiers::new(config(), clock.clone());
+
+        modifiers.activate(active(Modifier::Delay));
+        assert_eq!(modifiers.apply(Button::A), None);
+        assert_eq!(modifiers.next_delayed(), Some(Duration::from_millis(500)));
+        assert_eq!(modifiers.take_delayed(), vec![]);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(modifiers.take_delayed(), vec![Button::A]);
+        assert_eq!(modifiers.next_delayed(), None);
+
+        // Every press is random, but still a button.
+        modifiers.activate(active(Modifier::Random));
+        for _ in 0..10 {
+            modifiers.apply(Button::Up);
+        }
+        clock.advance(Duration::from_millis(500));
+        let pressed = modifiers.take_delayed();
+        assert_eq!(pressed.len(), 10);
+        assert!(pressed.iter().all(|button| Button::ALL.contains(button)));
+    }
+}
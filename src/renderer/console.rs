@@ -1,9 +1,14 @@
 use super::Renderer;
 use crate::command::Command;
-use crate::command_input::Input;
-use crate::vote_system::VoteSystem;
-
-use stats::Frequencies;
+use crate::command_input::{ConnectionState, Input, User};
+use crate::idle::IdleStatus;
+use crate::input_filter::{DroppedInput, FilterCounter};
+use crate::latency::InputLatency;
+use crate::logging::logs_inputs;
+use crate::mediator::PauseStatus;
+use crate::modifier::ActiveModifier;
+use crate::scheduler::VoteSystemLock;
+use crate::vote_system::{VoteSystem, VoteSystemSnapshot};
 
 pub struct ConsoleRenderer {}
 
@@ -15,16 +20,30 @@ impl ConsoleRenderer {
 
 impl Renderer for ConsoleRenderer {
     fn new_input(&mut self, input: Input) {
-        println!("{:?}", input);
+        if logs_inputs() {
+            println!("{:?}", input);
+        }
     }
 
+    fn new_dropped_input(&mut self, _dropped: DroppedInput) {}
+    fn new_flagged_user(&mut self, _user: User) {}
+    fn new_filter_counters(&mut self, _counters: Vec<FilterCounter>) {}
+    fn new_connection_state(&mut self, _state: ConnectionState) {}
+    fn new_input_latency(&mut self, _latency: InputLatency) {}
+
     fn new_command(&mut self, command: Command) {
-        println!("{:?}", command);
+        if logs_inputs() {
+            println!("{:?}", command);
+        }
     }
 
-    fn new_vote_system(&mut self, vote_system: VoteSystem) {}
-    fn new_vote_system_percentage(&mut self, pct: Option<f64>) {}
-    fn new_vote_system_democracy_partial_results(&mut self, t: u64, results: Frequencies<Command>) {
-    }
-    fn new_vote_system_change_secs_remaining(&mut self, t: u64) {}
+    fn new_vote_system(&mut self, _vote_system: VoteSystem) {}
+    fn new_vote_system_votes(&mut self, _votes: Vec<(VoteSystem, u64)>) {}
+    fn new_vote_system_lock(&mut self, _lock: Option<VoteSystemLock>) {}
+    fn new_vote_system_snapshot(&mut self, _snapshot: VoteSystemSnapshot) {}
+    fn new_vote_system_round_cancelled(&mut self, _votes: u64) {}
+    fn new_idle_status(&mut self, _status: Option<IdleStatus>) {}
+    fn new_modifiers(&mut self, _modifiers: Vec<ActiveModifier>) {}
+    fn new_pause_status(&mut self, _status: Option<PauseStatus>) {}
+    fn new_vote_system_change_secs_remaining(&mut self, _t: u64) {}
 }
@@ -1,14 +1,26 @@
 use super::Renderer;
 use crate::command::Command;
-use crate::command_input::Input;
-use crate::vote_system::VoteSystem;
+use crate::command_input::{
+    tokens_match, ConnectionState, HttpInputSender, Input, InputMessage, User,
+};
+use crate::idle::IdleStatus;
+use crate::input_filter::{DroppedInput, FilterCounter};
+use crate::latency::InputLatency;
+use crate::mediator::{MediatorControl, MediatorControlSender, PauseStatus};
+use crate::modifier::ActiveModifier;
+use crate::scheduler::VoteSystemLock;
+use crate::user_list::UserList;
+use crate::vote_system::{VoteSystem, VoteSystemSnapshot};
 
-use hyper::{Body, Response, StatusCode};
+use hyper::header::AUTHORIZATION;
+use hyper::rt::{Future, Stream};
+use hyper::{Body, HeaderMap, Response, StatusCode};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use gotham::handler::IntoResponse;
-use gotham::helpers::http::response::create_response;
+use gotham::handler::{HandlerFuture, IntoHandlerError};
+use gotham::helpers::http::response::{create_empty_response, create_response};
 use gotham::middleware::state::StateMiddleware;
 use gotham::pipeline::single::single_pipeline;
 use gotham::pipeline::single_middleware;
@@ -18,30 +30,86 @@ use gotham::state::{FromState, State};
 use std::collections::vec_deque::VecDeque;
 use std::thread;
 
+use std::panic::RefUnwindSafe;
 use std::sync::{Arc, Mutex};
 
-use stats::Frequencies;
+// Posted inputs are small JSON objects, so longer bodies are refused.
+const MAX_INPUT_BYTES: usize = 4096;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HttpConfig {
+    // Operators send it to use the control endpoints, which are refused without one.
+    pub control_token: Option<String>,
+}
 
 #[derive(Clone, StateData)]
 pub struct HTTPRenderer {
     pub last_inputs_vec: Arc<Mutex<VecDeque<Input>>>,
+    pub last_dropped_inputs: Arc<Mutex<VecDeque<DroppedInput>>>,
+    pub flagged_users: Arc<Mutex<Vec<User>>>,
+    pub last_filter_counters: Arc<Mutex<Vec<FilterCounter>>>,
+    pub last_connection_state: Arc<Mutex<Option<ConnectionState>>>,
+    pub last_input_latency: Arc<Mutex<Option<InputLatency>>>,
     pub last_vote_system: Arc<Mutex<Option<VoteSystem>>>,
-    pub last_vote_system_percentage: Arc<Mutex<Option<f64>>>,
-    pub last_vote_system_partial_results: Arc<Mutex<Option<Frequencies<Command>>>>,
-    pub last_vote_system_elapsed_time: Arc<Mutex<u64>>,
+    pub last_vote_system_votes: Arc<Mutex<Vec<(VoteSystem, u64)>>>,
+    pub last_vote_system_lock: Arc<Mutex<Option<VoteSystemLock>>>,
+    pub last_vote_system_snapshot: Arc<Mutex<Option<VoteSystemSnapshot>>>,
+    pub last_vote_system_round_cancelled: Arc<Mutex<Option<u64>>>,
+    pub last_idle_status: Arc<Mutex<Option<IdleStatus>>>,
+    pub last_modifiers: Arc<Mutex<Vec<ActiveModifier>>>,
+    pub last_pause_status: Arc<Mutex<Option<PauseStatus>>>,
     pub last_vote_system_change_remaining_secs: Arc<Mutex<u64>>,
+    tx_control: Arc<Mutex<MediatorControlSender>>,
+    control_token: Option<String>,
+    user_list: Arc<UserList>,
+    http_inputs: Vec<HttpInputSender>,
+}
+
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct PauseQuery {
+    reason: Option<String>,
+    token: Option<String>,
+}
+
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct UserQuery {
+    user: String,
+    // Only used by bans, which are permanent without it.
+    secs: Option<u64>,
+    token: Option<String>,
+}
+
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct TokenQuery {
+    // Can also be sent as an "Authorization: Bearer" header.
+    token: Option<String>,
 }
 
 #[derive(Serialize)]
 struct RendererData {
     last_inputs: VecDeque<Input>,
+    last_dropped_inputs: VecDeque<DroppedInput>,
+    flagged_users: Vec<User>,
+    last_filter_counters: Vec<FilterCounter>,
+    last_connection_state: Option<ConnectionState>,
+    last_input_latency: Option<InputLatency>,
     last_vote_system: Option<VoteSystem>,
-    last_vote_system_percentage: Option<f64>,
-    last_vote_system_partial_results: Option<Vec<(Command, u64)>>,
-    last_vote_system_elapsed_time: u64,
+    last_vote_system_votes: Vec<(VoteSystem, u64)>,
+    last_vote_system_lock: Option<VoteSystemLock>,
+    last_vote_system_snapshot: Option<VoteSystemSnapshot>,
+    last_vote_system_round_cancelled: Option<u64>,
+    last_idle_status: Option<IdleStatus>,
+    last_modifiers: Vec<ActiveModifier>,
+    last_pause_status: Option<PauseStatus>,
     last_vote_system_change_remaining_secs: u64,
 }
 
+// Gotham wants its state to be unwind safe, which the clock the user list and the inputs
+// read from doesn't promise. Everything a handler changes is behind a mutex, so a panic
+// can't leave it half done.
+impl RefUnwindSafe for HTTPRenderer {}
+
 impl HTTPRenderer {
     fn response_json<T: ?Sized>(state: &State, s: &T) -> Response<Body>
     where
@@ -68,24 +136,25 @@ impl HTTPRenderer {
     fn data(state: State) -> (State, Response<Body>) {
         let response = {
             let this = HTTPRenderer::borrow_from(&state);
-            let mut _results = this.last_vote_system_partial_results.lock().unwrap();
-            let partial = _results.clone().map(|f| {
-                f.most_frequent()
-                    .iter()
-                    .map(|&(k, v)| (k.clone(), v))
-                    .collect()
-            });
 
             let renderer_data = RendererData {
                 last_inputs: this.last_inputs_vec.lock().unwrap().clone(),
+                last_dropped_inputs: this.last_dropped_inputs.lock().unwrap().clone(),
+                flagged_users: this.flagged_users.lock().unwrap().clone(),
+                last_filter_counters: this.last_filter_counters.lock().unwrap().clone(),
+                last_connection_state: this.last_connection_state.lock().unwrap().clone(),
+                last_input_latency: this.last_input_latency.lock().unwrap().clone(),
                 last_vote_system: this.last_vote_system.lock().unwrap().clone(),
-                last_vote_system_percentage: this
-                    .last_vote_system_percentage
+                last_vote_system_votes: this.last_vote_system_votes.lock().unwrap().clone(),
+                last_vote_system_lock: this.last_vote_system_lock.lock().unwrap().clone(),
+                last_vote_system_snapshot: this.last_vote_system_snapshot.lock().unwrap().clone(),
+                last_vote_system_round_cancelled: *this
+                    .last_vote_system_round_cancelled
                     .lock()
-                    .unwrap()
-                    .clone(),
-                last_vote_system_partial_results: partial,
-                last_vote_system_elapsed_time: *this.last_vote_system_elapsed_time.lock().unwrap(),
+                    .unwrap(),
+                last_idle_status: this.last_idle_status.lock().unwrap().clone(),
+                last_modifiers: this.last_modifiers.lock().unwrap().clone(),
+                last_pause_status: this.last_pause_status.lock().unwrap().clone(),
                 last_vote_system_change_remaining_secs: *this
                     .last_vote_system_change_remaining_secs
                     .lock()
@@ -108,6 +177,162 @@ impl HTTPRenderer {
         (state, response)
     }
 
+    fn vote_system_snapshot(state: State) -> (State, Response<Body>) {
+        let response = {
+            let this = HTTPRenderer::borrow_from(&state);
+
+            HTTPRenderer::response_json(&state, &*this.last_vote_system_snapshot.lock().unwrap())
+        };
+
+        (state, response)
+    }
+
+    // The token from the query string, or else the "Authorization: Bearer" header.
+    fn request_token(state: &State, query: Option<String>) -> Option<String> {
+        query.or_else(|| {
+            HeaderMap::borrow_from(state)
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::to_owned)
+        })
+    }
+
+    // Whether the request comes from an operator. Without a control token, nobody does.
+    fn authorized(state: &State, token: Option<String>) -> bool {
+        let this = HTTPRenderer::borrow_from(state);
+
+        match (
+            &this.control_token,
+            HTTPRenderer::request_token(state, token),
+        ) {
+            (Some(expected), Some(token)) => tokens_match(expected, &token),
+            _ => false,
+        }
+    }
+
+    fn send_control(
+        state: &State,
+        token: Option<String>,
+        control: MediatorControl,
+    ) -> Response<Body> {
+        let this = HTTPRenderer::borrow_from(state);
+
+        if !HTTPRenderer::authorized(state, token) {
+            return create_empty_response(state, StatusCode::UNAUTHORIZED);
+        }
+
+        match this.tx_control.lock().unwrap().send(control) {
+            Ok(()) => create_empty_response(state, StatusCode::NO_CONTENT),
+            Err(_) => create_empty_response(state, StatusCode::SERVICE_UNAVAILABLE),
+        }
+    }
+
+    fn pause(mut state: State) -> (State, Response<Body>) {
+        let query = PauseQuery::take_from(&mut state);
+        let control = MediatorControl::Pause(query.reason);
+        let response = HTTPRenderer::send_control(&state, query.token, control);
+
+        (state, response)
+    }
+
+    fn resume(mut state: State) -> (State, Response<Body>) {
+        let query = TokenQuery::take_from(&mut state);
+        let response = HTTPRenderer::send_control(&state, query.token, MediatorControl::Resume);
+
+        (state, response)
+    }
+
+    // Only operators see who's banned or ignored.
+    fn users(mut state: State) -> (State, Response<Body>) {
+        let query = TokenQuery::take_from(&mut state);
+        let response = if HTTPRenderer::authorized(&state, query.token) {
+            let this = HTTPRenderer::borrow_from(&state);
+
+            HTTPRenderer::response_json(&state, &this.user_list.snapshot())
+        } else {
+            create_empty_response(&state, StatusCode::UNAUTHORIZED)
+        };
+
+        (state, response)
+    }
+
+    fn user_control(
+        mut state: State,
+        control: fn(UserQuery) -> MediatorControl,
+    ) -> (State, Response<Body>) {
+        let query = UserQuery::take_from(&mut state);
+        let token = query.token.clone();
+        let response = HTTPRenderer::send_control(&state, token, control(query));
+
+        (state, response)
+    }
+
+    fn ban(state: State) -> (State, Response<Body>) {
+        HTTPRenderer::user_control(state, |q| MediatorControl::Ban(q.user, q.secs))
+    }
+
+    fn unban(state: State) -> (State, Response<Body>) {
+        HTTPRenderer::user_control(state, |q| MediatorControl::Unban(q.user))
+    }
+
+    fn ignore(state: State) -> (State, Response<Body>) {
+        HTTPRenderer::user_control(state, |q| MediatorControl::Ignore(q.user))
+    }
+
+    fn unignore(state: State) -> (State, Response<Body>) {
+        HTTPRenderer::user_control(state, |q| MediatorControl::Unignore(q.user))
+    }
+
+    fn send_input(state: &State, token: Option<String>, body: &[u8]) -> Response<Body> {
+        let this = HTTPRenderer::borrow_from(state);
+        let input = token.and_then(|token| {
+            this.http_inputs
+                .iter()
+                .find(|input| input.authorizes(&token))
+        });
+        let input = match input {
+            Some(input) => input,
+            None => return create_empty_response(state, StatusCode::UNAUTHORIZED),
+        };
+
+        match serde_json::from_slice::<InputMessage>(body) {
+            Ok(ref message) if input.send(message) => {
+                create_empty_response(state, StatusCode::NO_CONTENT)
+            }
+            Ok(_) => create_empty_response(state, StatusCode::UNPROCESSABLE_ENTITY),
+            Err(_) => create_empty_response(state, StatusCode::BAD_REQUEST),
+        }
+    }
+
+    fn input(mut state: State) -> Box<HandlerFuture> {
+        let query = TokenQuery::take_from(&mut state);
+        let token = HTTPRenderer::request_token(&state, query.token);
+
+        // Stops reading once the body is too long for an input, keeping at most one chunk more.
+        let mut received = 0;
+        let body = Body::take_from(&mut state)
+            .take_while(move |chunk| {
+                let within = received <= MAX_INPUT_BYTES;
+                received += chunk.len();
+                Ok(within)
+            })
+            .concat2()
+            .then(|body| match body {
+                Ok(ref body) if body.len() > MAX_INPUT_BYTES => {
+                    let response = create_empty_response(&state, StatusCode::PAYLOAD_TOO_LARGE);
+                    Ok((state, response))
+                }
+                Ok(body) => {
+                    let response = HTTPRenderer::send_input(&state, token, &body);
+                    Ok((state, response))
+                }
+                Err(e) => Err((state, e.into_handler_error())),
+            });
+
+        Box::new(body)
+    }
+
     fn router(&self) -> Router {
         let middleware = StateMiddleware::new(self.clone());
         let pipeline = single_middleware(middleware);
@@ -116,21 +341,74 @@ impl HTTPRenderer {
         build_router(chain, pipelines, |route| {
             route.get("/last_inputs").to(HTTPRenderer::last_inputs);
             route.get("/vote_system").to(HTTPRenderer::vote_system);
+            route
+                .get("/vote_system/snapshot")
+                .to(HTTPRenderer::vote_system_snapshot);
             route.get("/data").to(HTTPRenderer::data);
+            route
+                .post("/pause")
+                .with_query_string_extractor::<PauseQuery>()
+                .to(HTTPRenderer::pause);
+            route
+                .post("/resume")
+                .with_query_string_extractor::<TokenQuery>()
+                .to(HTTPRenderer::resume);
+            route
+                .get("/users")
+                .with_query_string_extractor::<TokenQuery>()
+                .to(HTTPRenderer::users);
+            route
+                .post("/users/ban")
+                .with_query_string_extractor::<UserQuery>()
+                .to(HTTPRenderer::ban);
+            route
+                .post("/users/unban")
+                .with_query_string_extractor::<UserQuery>()
+                .to(HTTPRenderer::unban);
+            route
+                .post("/users/ignore")
+                .with_query_string_extractor::<UserQuery>()
+                .to(HTTPRenderer::ignore);
+            route
+                .post("/users/unignore")
+                .with_query_string_extractor::<UserQuery>()
+                .to(HTTPRenderer::unignore);
+            route
+                .post("/inputs")
+                .with_query_string_extractor::<TokenQuery>()
+                .to(HTTPRenderer::input);
 
             route.get("/").to_file("static/index.html");
             route.get("static/*").to_dir("static");
         })
     }
 
-    pub fn new() -> Self {
+    pub fn new(
+        config: HttpConfig,
+        tx_control: MediatorControlSender,
+        user_list: Arc<UserList>,
+        http_inputs: Vec<HttpInputSender>,
+    ) -> Self {
         HTTPRenderer {
             last_inputs_vec: Arc::new(Mutex::new(VecDeque::new())),
+            last_dropped_inputs: Arc::new(Mutex::new(VecDeque::new())),
+            flagged_users: Arc::new(Mutex::new(Vec::new())),
+            last_filter_counters: Arc::new(Mutex::new(Vec::new())),
+            last_connection_state: Arc::new(Mutex::new(None)),
+            last_input_latency: Arc::new(Mutex::new(None)),
             last_vote_system: Arc::new(Mutex::new(None)),
-            last_vote_system_percentage: Arc::new(Mutex::new(None)),
-            last_vote_system_partial_results: Arc::new(Mutex::new(None)),
-            last_vote_system_elapsed_time: Arc::new(Mutex::new(0)),
+            last_vote_system_votes: Arc::new(Mutex::new(Vec::new())),
+            last_vote_system_lock: Arc::new(Mutex::new(None)),
+            last_vote_system_snapshot: Arc::new(Mutex::new(None)),
+            last_vote_system_round_cancelled: Arc::new(Mutex::new(None)),
+            last_idle_status: Arc::new(Mutex::new(None)),
+            last_modifiers: Arc::new(Mutex::new(Vec::new())),
+            last_pause_status: Arc::new(Mutex::new(None)),
             last_vote_system_change_remaining_secs: Arc::new(Mutex::new(0)),
+            tx_control: Arc::new(Mutex::new(tx_control)),
+            control_token: config.control_token,
+            user_list,
+            http_inputs,
         }
     }
 
@@ -150,19 +428,67 @@ impl Renderer for HTTPRenderer {
         _vec.truncate(20);
     }
 
-    fn new_command(&mut self, cmd: Command) {}
+    fn new_dropped_input(&mut self, dropped: DroppedInput) {
+        let mut _vec = self.last_dropped_inputs.lock().unwrap();
+        _vec.push_front(dropped);
+        _vec.truncate(20);
+    }
+
+    fn new_flagged_user(&mut self, user: User) {
+        let mut _flagged = self.flagged_users.lock().unwrap();
+        // Only the latest, each once.
+        _flagged.retain(|flagged| *flagged != user);
+        _flagged.insert(0, user);
+        _flagged.truncate(20);
+    }
+
+    fn new_filter_counters(&mut self, counters: Vec<FilterCounter>) {
+        *self.last_filter_counters.lock().unwrap() = counters;
+    }
+
+    fn new_connection_state(&mut self, state: ConnectionState) {
+        *self.last_connection_state.lock().unwrap() = Some(state);
+    }
+
+    fn new_input_latency(&mut self, latency: InputLatency) {
+        *self.last_input_latency.lock().unwrap() = Some(latency);
+    }
+
+    fn new_command(&mut self, _cmd: Command) {}
 
     fn new_vote_system(&mut self, vote_system: VoteSystem) {
         *self.last_vote_system.lock().unwrap() = Some(vote_system);
+        // The snapshot belongs to the previous system, if any.
+        *self.last_vote_system_snapshot.lock().unwrap() = None;
+        *self.last_vote_system_round_cancelled.lock().unwrap() = None;
+    }
+
+    fn new_vote_system_votes(&mut self, votes: Vec<(VoteSystem, u64)>) {
+        *self.last_vote_system_votes.lock().unwrap() = votes;
+    }
+
+    fn new_vote_system_lock(&mut self, lock: Option<VoteSystemLock>) {
+        *self.last_vote_system_lock.lock().unwrap() = lock;
+    }
+
+    fn new_vote_system_snapshot(&mut self, snapshot: VoteSystemSnapshot) {
+        *self.last_vote_system_snapshot.lock().unwrap() = Some(snapshot);
+    }
+
+    fn new_vote_system_round_cancelled(&mut self, votes: u64) {
+        *self.last_vote_system_round_cancelled.lock().unwrap() = Some(votes);
+    }
+
+    fn new_idle_status(&mut self, status: Option<IdleStatus>) {
+        *self.last_idle_status.lock().unwrap() = status;
     }
 
-    fn new_vote_system_percentage(&mut self, pct: Option<f64>) {
-        *self.last_vote_system_percentage.lock().unwrap() = pct;
+    fn new_modifiers(&mut self, modifiers: Vec<ActiveModifier>) {
+        *self.last_modifiers.lock().unwrap() = modifiers;
     }
 
-    fn new_vote_system_democracy_partial_results(&mut self, t: u64, results: Frequencies<Command>) {
-        *self.last_vote_system_partial_results.lock().unwrap() = Some(results);
-        *self.last_vote_system_elapsed_time.lock().unwrap() = t;
+    fn new_pause_status(&mut self, status: Option<PauseStatus>) {
+        *self.last_pause_status.lock().unwrap() = status;
     }
 
     fn new_vote_system_change_secs_remaining(&mut self, t: u64) {
@@ -1,14 +1,29 @@
 use crate::command::Command;
-use crate::command_input::Input;
-use crate::vote_system::VoteSystem;
-use stats::Frequencies;
+use crate::command_input::{ConnectionState, Input, User};
+use crate::idle::IdleStatus;
+use crate::input_filter::{DroppedInput, FilterCounter};
+use crate::latency::InputLatency;
+use crate::mediator::PauseStatus;
+use crate::modifier::ActiveModifier;
+use crate::scheduler::VoteSystemLock;
+use crate::vote_system::{VoteSystem, VoteSystemSnapshot};
 
 pub trait Renderer {
     fn new_input(&mut self, input: Input);
+    fn new_dropped_input(&mut self, dropped: DroppedInput);
+    fn new_flagged_user(&mut self, user: User);
+    fn new_filter_counters(&mut self, counters: Vec<FilterCounter>);
+    fn new_connection_state(&mut self, state: ConnectionState);
+    fn new_input_latency(&mut self, latency: InputLatency);
     fn new_command(&mut self, command: Command);
     fn new_vote_system(&mut self, vote_system: VoteSystem);
-    fn new_vote_system_percentage(&mut self, pct: Option<f64>);
-    fn new_vote_system_democracy_partial_results(&mut self, t: u64, results: Frequencies<Command>);
+    fn new_vote_system_votes(&mut self, votes: Vec<(VoteSystem, u64)>);
+    fn new_vote_system_lock(&mut self, lock: Option<VoteSystemLock>);
+    fn new_vote_system_snapshot(&mut self, snapshot: VoteSystemSnapshot);
+    fn new_vote_system_round_cancelled(&mut self, votes: u64);
+    fn new_idle_status(&mut self, status: Option<IdleStatus>);
+    fn new_modifiers(&mut self, modifiers: Vec<ActiveModifier>);
+    fn new_pause_status(&mut self, status: Option<PauseStatus>);
     fn new_vote_system_change_secs_remaining(&mut self, t: u64);
 }
 
@@ -16,4 +31,4 @@ pub mod console;
 pub use console::ConsoleRenderer;
 
 pub mod http;
-pub use http::HTTPRenderer;
+pub use http::{HTTPRenderer, HttpConfig};
@@ -0,0 +1,359 @@
+use crate::vote_system::{VoteSystem, VoteSystemRegistry};
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+// A segment where a vote system is forced, like a daily "democracy hour". It either starts
+// at a daily UTC time, on a cron schedule, or after some time since the session started,
+// optionally repeating.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScheduleEntry {
+    pub vote_system: String,
+    pub daily_at: Option<String>,
+    // "minute hour day-of-month month day-of-week", in UTC.
+    pub cron: Option<String>,
+    pub after_mins: Option<u64>,
+    pub every_mins: Option<u64>,
+    pub duration_mins: u64,
+    // Locked segments suspend mode voting until they end.
+    #[serde(default = "default_lock")]
+    pub lock: bool,
+}
+
+fn default_lock() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct VoteSystemLock {
+    pub system: VoteSystem,
+    // Unix timestamp, in seconds. Locks without an end last until they're lifted.
+    pub ends_at: Option<u64>,
+}
+
+// One run of an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    // Tells runs of the same entry apart. A unix timestamp, or the seconds since the
+    // session started for entries that count from it.
+    pub start: u64,
+    // Unix timestamp, in seconds.
+    pub ends_at: u64,
+}
+
+// An entry with its start parsed, since it's checked every second.
+pub struct ScheduledEntry {
+    pub entry: ScheduleEntry,
+    start: Start,
+}
+
+// When the segments of an entry start.
+enum Start {
+    Cron(Cron),
+    // In seconds since the session started.
+    Session { offset: u64, every: Option<u64> },
+}
+
+// The minutes a cron expression matches, one bit per value of each field.
+#[derive(Debug, PartialEq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Like cron, when both days are restricted either of them matching is enough.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(s: &str) -> Option<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // Both 0 and 7 are Sunday.
+        if weekdays & 1 << 7 != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Some(Cron {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn daily(s: &str) -> Option<Self> {
+        let (hours, minutes) = s.trim().split_once(':')?;
+        let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+
+        if hours < 24 && minutes < 60 {
+            Cron::parse(&format!("{} {} * * *", minutes, hours))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, unix: u64) -> bool {
+        let bit = |field: u64, value: u64| field & 1 << value != 0;
+        let days = unix / SECS_PER_DAY;
+        let (month, day) = month_and_day(days);
+        let weekday = (days + 4) % 7;
+
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => bit(self.days, day) || bit(self.weekdays, weekday),
+            _ => bit(self.days, day) && bit(self.weekdays, weekday),
+        };
+
+        bit(self.minutes, unix / 60 % 60)
+            && bit(self.hours, unix / 3600 % 24)
+            && bit(self.months, month)
+            && day_matches
+    }
+}
+
+// Fields like "*", "5", "1-5", "*/15" or "0,30".
+fn parse_field(s: &str, min: u64, max: u64) -> Option<u64> {
+    let mut bits = 0;
+
+    for part in s.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u64>().ok().filter(|&s| s > 0)?)),
+            None => (part, None),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+            None => {
+                let value = range.parse().ok()?;
+                (value, if step.is_some() { max } else { value })
+            }
+        };
+
+        if first < min || last > max || first > last {
+            return None;
+        }
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Some(bits)
+}
+
+// The month and day of the month, from the days since 1970-01-01.
+fn month_and_day(days: u64) -> (u64, u64) {
+    let day_of_era = (days + 719_468) % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Counting from March, so leap days come last.
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+
+    (if month < 10 { month + 3 } else { month - 9 }, day)
+}
+
+impl ScheduleEntry {
+    fn start(&self) -> Result<Start, String> {
+        let start = match (&self.daily_at, &self.cron, self.after_mins) {
+            (Some(daily_at), None, None) => Cron::daily(daily_at)
+                .map(Start::Cron)
+                .ok_or_else(|| format!("bad daily_at {:?}", daily_at)),
+            (None, Some(cron), None) => Cron::parse(cron)
+                .map(Start::Cron)
+                .ok_or_else(|| format!("bad cron {:?}", cron)),
+            (None, None, Some(after_mins)) => Ok(Start::Session {
+                offset: after_mins * 60,
+                every: self.every_mins.map(|every| every * 60),
+            }),
+            _ => Err("needs exactly one of daily_at, cron or after_mins".to_owned()),
+        }?;
+
+        match (&start, self.every_mins) {
+            (Start::Cron(_), Some(_)) => Err("every_mins only works with after_mins".to_owned()),
+            (_, Some(0)) => Err("every_mins can't be 0".to_owned()),
+            _ if self.duration_mins == 0 => Err("duration_mins can't be 0".to_owned()),
+            _ => Ok(start),
+        }
+    }
+
+    pub fn validate(&self, registry: &VoteSystemRegistry) -> Result<(), String> {
+        let error = |e: String| format!("schedule for {}: {}", self.vote_system, e);
+
+        self.start().map_err(error)?;
+        match registry.get(&self.vote_system) {
+            Some(_) => Ok(()),
+            None => Err(error("unknown vote system".to_owned())),
+        }
+    }
+
+    pub fn scheduled(self) -> Result<ScheduledEntry, String> {
+        let start = self.start()?;
+
+        Ok(ScheduledEntry { entry: self, start })
+    }
+}
+
+impl ScheduledEntry {
+    // The segment running now, if any.
+    pub fn active(&self, session_elapsed: Duration, now: u64) -> Option<Segment> {
+        let duration = self.entry.duration_mins * 60;
+
+        match self.start {
+            // The latest start that's still running, which may have been yesterday. Nothing
+            // started before the epoch, which is where a manual clock begins.
+            Start::Cron(ref cron) => (0..)
+                .map_while(|minutes_ago| (now - now % 60).checked_sub(minutes_ago * 60))
+                .take_while(|&start| start + duration > now)
+                .find(|&start| cron.matches(start))
+                .map(|start| Segment {
+                    start,
+                    ends_at: start + duration,
+                }),
+            Start::Session { offset, every } => {
+                let elapsed = session_elapsed.as_secs();
+                if elapsed < offset {
+                    return None;
+                }
+
+                let start = match every {
+                    Some(every) => offset + (elapsed - offset) / every * every,
+                    None => offset,
+                };
+
+                Some(now + (start + duration).checked_sub(elapsed)?)
+                    .filter(|&end| now < end)
+                    .map(|ends_at| Segment { start, ends_at })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2021-03-01, a Monday, at midnight UTC.
+    const MONDAY: u64 = 1_614_556_800;
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+
+    fn until(entry: &ScheduledEntry, session_elapsed: Duration, now: u64) -> Option<u64> {
+        entry
+            .active(session_elapsed, now)
+            .map(|segment| segment.ends_at)
+    }
+
+    fn entry(daily_at: Option<&str>, cron: Option<&str>, after_mins: Option<u64>) -> ScheduleEntry {
+        ScheduleEntry {
+            vote_system: "Democracy".to_owned(),
+            daily_at: daily_at.map(str::to_owned),
+            cron: cron.map(str::to_owned),
+            after_mins,
+            every_mins: None,
+            duration_mins: 60,
+            lock: true,
+        }
+    }
+
+    #[test]
+    fn daily_segments_run_past_midnight() {
+        let entry = entry(Some("23:30"), None, None).scheduled().unwrap();
+        let none = Duration::from_secs(0);
+
+        assert_eq!(until(&entry, none, MONDAY + 23 * HOUR), None);
+        assert_eq!(
+            until(&entry, none, MONDAY + 23 * HOUR + 30 * MINUTE),
+            Some(MONDAY + 24 * HOUR + 30 * MINUTE)
+        );
+        assert_eq!(
+            until(&entry, none, MONDAY + 24 * HOUR + 29 * MINUTE),
+            Some(MONDAY + 24 * HOUR + 30 * MINUTE)
+        );
+        assert_eq!(until(&entry, none, MONDAY + 24 * HOUR + 30 * MINUTE), None);
+    }
+
+    #[test]
+    fn cron_segments_start_on_matching_minutes() {
+        let none = Duration::from_secs(0);
+        // Weekdays at 20:00, and the first of the month at noon.
+        let weekdays = entry(None, Some("0 20 * * 1-5"), None).scheduled().unwrap();
+        let first = entry(None, Some("0 12 1 * *"), None).scheduled().unwrap();
+
+        assert_eq!(
+            until(&weekdays, none, MONDAY + 20 * HOUR + 59 * MINUTE),
+            Some(MONDAY + 21 * HOUR)
+        );
+        let saturday = MONDAY + 5 * SECS_PER_DAY;
+        assert_eq!(until(&weekdays, none, saturday + 20 * HOUR), None);
+
+        assert_eq!(
+            until(&first, none, MONDAY + 12 * HOUR),
+            Some(MONDAY + 13 * HOUR)
+        );
+        assert_eq!(until(&first, none, MONDAY + SECS_PER_DAY + 12 * HOUR), None);
+        // 2021-04-01, after the 31 days of March.
+        let april = MONDAY + 31 * SECS_PER_DAY;
+        assert!(until(&first, none, april + 12 * HOUR).is_some());
+
+        // Right after the epoch, there's no earlier minute to look back on.
+        let hourly = entry(None, Some("0 * * * *"), None).scheduled().unwrap();
+        assert_eq!(until(&hourly, none, 30), Some(HOUR));
+        assert_eq!(until(&weekdays, none, 30), None);
+    }
+
+    #[test]
+    fn session_segments_repeat() {
+        let entry = ScheduleEntry {
+            every_mins: Some(120),
+            duration_mins: 10,
+            ..entry(None, None, Some(30))
+        }
+        .scheduled()
+        .unwrap();
+        let at = |mins: u64| until(&entry, Duration::from_secs(mins * 60), MONDAY);
+
+        assert_eq!(at(29), None);
+        assert_eq!(at(30), Some(MONDAY + 10 * MINUTE));
+        assert_eq!(at(39), Some(MONDAY + MINUTE));
+        assert_eq!(at(40), None);
+        assert_eq!(at(155), Some(MONDAY + 5 * MINUTE));
+    }
+
+    #[test]
+    fn refuses_entries_that_would_never_run() {
+        let registry = VoteSystemRegistry::with_builtin_systems(&Default::default());
+
+        assert!(entry(Some("20:00"), None, None).validate(&registry).is_ok());
+        assert!(entry(Some("25:00"), None, None)
+            .validate(&registry)
+            .is_err());
+        assert!(entry(None, Some("0 20 * *"), None)
+            .validate(&registry)
+            .is_err());
+        assert!(entry(None, Some("*/0 * * * *"), None)
+            .validate(&registry)
+            .is_err());
+        assert!(entry(None, None, None).validate(&registry).is_err());
+        assert!(entry(Some("20:00"), None, Some(5))
+            .validate(&registry)
+            .is_err());
+        assert!(ScheduleEntry {
+            vote_system: "Monarchy".to_owned(),
+            ..entry(None, None, Some(5))
+        }
+        .validate(&registry)
+        .is_err());
+    }
+}
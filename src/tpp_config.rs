@@ -1,9 +1,88 @@
+use crate::command_input::{ConnectionConfig, IrcConfig, Login, RecorderConfig, SourceConfig};
+use crate::logging::LoggingConfig;
+use crate::mediator::MediatorConfig;
+use crate::moderation::ModerationConfig;
+use crate::renderer::HttpConfig;
+use crate::user_list::UserListConfig;
+use crate::vote_system::VoteSystemsConfig;
 use serde::Deserialize;
+use std::fs;
 
 #[derive(Debug, Deserialize)]
 pub struct TPPConfig {
-    pub username: String,
-    pub oauth_token: String,
+    // Without a username, chat is read anonymously.
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub oauth_token: Option<String>,
+    // Where to read the token from instead, to keep it out of the settings.
+    #[serde(default)]
+    pub oauth_token_file: Option<String>,
+    // Where inputs are read from. Defaults to the username's own channel.
+    #[serde(default)]
+    pub channels: Vec<String>,
+    // Reads inputs from this IRC server, instead of Twitch.
+    #[serde(default)]
+    pub irc: Option<IrcConfig>,
+    // Reads from all of these at once, instead of a single channel or server.
+    #[serde(default)]
+    pub inputs: Vec<SourceConfig>,
+    #[serde(default)]
+    pub connection: ConnectionConfig,
+    #[serde(default)]
+    pub vote_systems: VoteSystemsConfig,
+    #[serde(default)]
+    pub mediator: MediatorConfig,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    #[serde(default)]
+    pub users: UserListConfig,
+    #[serde(default)]
+    pub recorder: RecorderConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+}
+
+impl TPPConfig {
+    pub fn login(&self) -> Result<Login, String> {
+        let username = match self.username {
+            Some(ref username) => username.clone(),
+            None => return Ok(Login::Anonymous),
+        };
+
+        let oauth_token = match (&self.oauth_token, &self.oauth_token_file) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "both oauth_token and oauth_token_file are set for {}, keep only one",
+                    username
+                ))
+            }
+            (Some(token), None) => token.clone(),
+            (None, Some(path)) => fs::read_to_string(path)
+                .map_err(|e| format!("couldn't read {}: {}", path, e))?
+                .trim()
+                .to_owned(),
+            (None, None) => return Err(format!("no oauth_token for {}", username)),
+        };
+
+        Ok(Login::Account {
+            username,
+            oauth_token,
+        })
+    }
+
+    pub fn channels(&self) -> Result<Vec<String>, String> {
+        if !self.channels.is_empty() {
+            return Ok(self.channels.clone());
+        }
+
+        match self.username {
+            Some(ref username) => Ok(vec![username.clone()]),
+            None => Err("no channels to join, and no username to default to".to_owned()),
+        }
+    }
 }
 
 // Uhh maybe something?
@@ -0,0 +1,296 @@
+use crate::clock::SharedClock;
+use crate::command_input::User;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct UserListConfig {
+    // Where the lists are kept between sessions. Without a path, they only last a session.
+    pub path: Option<String>,
+}
+
+impl Default for UserListConfig {
+    fn default() -> Self {
+        UserListConfig {
+            path: Some("users.json".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Lists {
+    // Other bots and such, whose messages are never inputs.
+    ignored: BTreeSet<User>,
+    // Banned users, with the unix timestamp their ban ends at, if it does.
+    banned: BTreeMap<User, Option<u64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserListSnapshot {
+    pub ignored: Vec<User>,
+    pub banned: Vec<(User, Option<u64>)>,
+    // Bans followed from the chat service this session.
+    pub chat_banned: Vec<(User, Option<u64>)>,
+    // Inputs dropped this session, by user.
+    pub dropped: Vec<(User, u64)>,
+}
+
+// Users whose inputs are dropped, as they're read from chat. Names are case insensitive.
+pub struct UserList {
+    // Wakes up the thread that writes the lists, so nobody waits on the disk. None when
+    // they're not kept.
+    tx_save: Option<Sender<()>>,
+    clock: SharedClock,
+    lists: Arc<Mutex<Lists>>,
+    // Bans the chat service made itself. They're never written, since the service doesn't
+    // tell when a ban is lifted, so they only last a session.
+    chat_banned: Mutex<BTreeMap<User, Option<u64>>>,
+    dropped: Mutex<HashMap<User, u64>>,
+}
+
+impl UserList {
+    pub fn load(config: &UserListConfig, clock: SharedClock) -> Self {
+        let lists = match config.path {
+            Some(ref path) => match fs::read_to_string(path) {
+                Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                    println!("UserList::load: couldn't parse {}: {}", path, e);
+                    Lists::default()
+                }),
+                Err(_) => Lists::default(),
+            },
+            None => Lists::default(),
+        };
+
+        let lists = Arc::new(Mutex::new(lists));
+        let tx_save = config
+            .path
+            .clone()
+            .map(|path| Self::spawn_saver(path, lists.clone()));
+
+        UserList {
+            tx_save,
+            clock,
+            lists,
+            chat_banned: Mutex::new(BTreeMap::new()),
+            dropped: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Writes the lists whenever they change, once for each burst of changes. Stops when
+    // the list is dropped.
+    fn spawn_saver(path: String, lists: Arc<Mutex<Lists>>) -> Sender<()> {
+        let (tx_save, rx_save) = channel();
+
+        thread::spawn(move || {
+            while rx_save.recv().is_ok() {
+                while rx_save.try_recv().is_ok() {}
+
+                let contents = serde_json::to_string_pretty(&*lists.lock().unwrap()).unwrap();
+                if let Err(e) = fs::write(&path, contents) {
+                    println!("UserList::save: couldn't write {}: {}", path, e);
+                }
+            }
+        });
+
+        tx_save
+    }
+
+    fn save(&self) {
+        if let Some(ref tx_save) = self.tx_save {
+            tx_save.send(()).unwrap();
+        }
+    }
+
+    // Whether the user's inputs go through. Dropped inputs are counted.
+    pub fn allows(&self, user: &str) -> bool {
+        let user = user.to_lowercase();
+        let mut lists = self.lists.lock().unwrap();
+
+        let banned = match lists.banned.get(&user) {
+            Some(&Some(until)) if until <= self.clock.unix_now() => {
+                lists.banned.remove(&user);
+                self.save();
+                false
+            }
+            Some(_) => true,
+            None => false,
+        };
+
+        let banned = banned || {
+            let mut chat_banned = self.chat_banned.lock().unwrap();
+            match chat_banned.get(&user) {
+                Some(&Some(until)) if until <= self.clock.unix_now() => {
+                    chat_banned.remove(&user);
+                    false
+                }
+                Some(_) => true,
+                None => false,
+            }
+        };
+
+        if !banned && !lists.ignored.contains(&user) {
+            return true;
+        }
+
+        *self.dropped.lock().unwrap().entry(user).or_insert(0) += 1;
+        false
+    }
+
+    // Bans for some seconds, or for good. Returns the ban it replaced, if any.
+    pub fn ban(&self, user: &str, secs: Option<u64>) -> Option<Option<u64>> {
+        let until = secs.map(|secs| self.clock.unix_now() + secs);
+        let mut lists = self.lists.lock().unwrap();
+
+        let previous = lists.banned.insert(user.to_lowercase(), until);
+        self.save();
+
+        previous
+    }
+
+    // Follows a ban or timeout from the chat service, for this session only.
+    pub fn ban_from_chat(&self, user: &str, secs: Option<u64>) {
+        let until = secs.map(|secs| self.clock.unix_now() + secs);
+        self.chat_banned
+            .lock()
+            .unwrap()
+            .insert(user.to_lowercase(), until);
+    }
+
+    // Returns the ban that was lifted, if any. Lifts the chat service's ban too, but only
+    // returns it when there was no other.
+    pub fn unban(&self, user: &str) -> Option<Option<u64>> {
+        let chat_ban = self
+            .chat_banned
+            .lock()
+            .unwrap()
+            .remove(&user.to_lowercase());
+        let mut lists = self.lists.lock().unwrap();
+
+        let previous = lists.banned.remove(&user.to_lowercase());
+        if previous.is_some() {
+            self.save();
+        }
+
+        previous.or(chat_ban)
+    }
+
+    // Returns whether the user wasn't ignored yet.
+    pub fn ignore(&self, user: &str) -> bool {
+        let mut lists = self.lists.lock().unwrap();
+
+        let added = lists.ignored.insert(user.to_lowercase());
+        if added {
+            self.save();
+        }
+
+        added
+    }
+
+    // Returns whether the user was ignored.
+    pub fn unignore(&self, user: &str) -> bool {
+        let mut lists = self.lists.lock().unwrap();
+
+        let removed = lists.ignored.remove(&user.to_lowercase());
+        if removed {
+            self.save();
+        }
+
+        removed
+    }
+
+    pub fn snapshot(&self) -> UserListSnapshot {
+        let lists = self.lists.lock().unwrap();
+        let mut dropped: Vec<(User, u64)> = self
+            .dropped
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(user, &count)| (user.clone(), count))
+            .collect();
+        dropped.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        UserListSnapshot {
+            ignored: lists.ignored.iter().cloned().collect(),
+            banned: lists
+                .banned
+                .iter()
+                .map(|(user, &until)| (user.clone(), until))
+                .collect(),
+            chat_banned: self
+                .chat_banned
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(user, &until)| (user.clone(), until))
+                .collect(),
+            dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn timed_bans_run_out_and_drops_are_counted() {
+        let clock = Arc::new(ManualClock::new());
+        let user_list = UserList::load(&UserListConfig { path: None }, clock.clone());
+
+        assert_eq!(user_list.ban("Troll", Some(60)), None);
+        assert!(user_list.ignore("somebot"));
+        assert!(!user_list.allows("troll"));
+        assert!(!user_list.allows("SomeBot"));
+
+        clock.advance(Duration::from_secs(60));
+        assert!(user_list.allows("Troll"));
+        assert!(!user_list.allows("somebot"));
+
+        let snapshot = user_list.snapshot();
+        assert!(snapshot.banned.is_empty());
+        assert_eq!(
+            snapshot.dropped,
+            vec![("somebot".to_owned(), 2), ("troll".to_owned(), 1)]
+        );
+    }
+
+    #[test]
+    fn keeps_the_lists_between_sessions() {
+        let clock = Arc::new(ManualClock::new());
+        let path = std::env::temp_dir().join(format!("tpp_rs_users_{}.json", std::process::id()));
+        let config = UserListConfig {
+            path: Some(path.to_string_lossy().into_owned()),
+        };
+        let _ = fs::remove_file(&path);
+
+        let user_list = UserList::load(&config, clock.clone());
+        user_list.ban("troll", None);
+        user_list.ban_from_chat("twitch:spammer", None);
+        user_list.ignore("somebot");
+        assert!(!user_list.allows("twitch:spammer"));
+        // The lists are written in the background, so they may take a moment.
+        drop(user_list);
+        let reloaded = (0..100)
+            .map(|_| {
+                thread::sleep(Duration::from_millis(10));
+                UserList::load(&config, clock.clone()).snapshot()
+            })
+            .find(|snapshot| !snapshot.ignored.is_empty())
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.banned, vec![("troll".to_owned(), None)]);
+        assert!(reloaded.chat_banned.is_empty());
+        assert_eq!(reloaded.ignored, vec!["somebot".to_owned()]);
+    }
+}
@@ -2,24 +2,31 @@ use crate::mediator::{MediatorUpdate, MediatorUpdateSender};
 
 use stats::Frequencies;
 use std::hash::Hash;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
 pub struct VoteCounter<T: Eq + Hash + Clone> {
     frequencies: Arc<Mutex<Frequencies<T>>>,
     tx_update: MediatorUpdateSender,
-    update_reference_element: T,
+    candidates: Vec<T>,
+    update: fn(Vec<(T, u64)>) -> MediatorUpdate,
 }
 
 impl<T> VoteCounter<T>
 where
     T: Eq + Hash + Clone,
 {
-    pub fn new(tx_update: MediatorUpdateSender, reference_element: T) -> Self {
+    // Every change sends the vote count of each candidate, in order, wrapped by `update`.
+    pub fn new(
+        tx_update: MediatorUpdateSender,
+        candidates: Vec<T>,
+        update: fn(Vec<(T, u64)>) -> MediatorUpdate,
+    ) -> Self {
         Self {
             frequencies: Arc::new(Mutex::new(Frequencies::new())),
-            tx_update: tx_update,
-            update_reference_element: reference_element,
+            tx_update,
+            candidates,
+            update,
         }
     }
 
@@ -27,11 +34,7 @@ where
         let mut _freq = self.frequencies.lock().unwrap();
         _freq.add(key);
 
-        self.tx_update
-            .send(MediatorUpdate::VoteSystemPercentageChange(Some(
-                self._percentage(self.update_reference_element.clone(), _freq),
-            )))
-            .unwrap();
+        self.send_breakdown(&_freq);
     }
 
     pub fn winner(&self) -> Option<T> {
@@ -43,23 +46,20 @@ where
             .map(|&(winner, _)| winner.clone())
     }
 
-    fn _percentage(&self, key: T, _freq: MutexGuard<Frequencies<T>>) -> f64 {
-        let count_key = _freq.count(&key);
+    fn send_breakdown(&self, _freq: &Frequencies<T>) {
+        let breakdown = self
+            .candidates
+            .iter()
+            .map(|candidate| (candidate.clone(), _freq.count(candidate)))
+            .collect();
 
-        let total: u64 = _freq.most_frequent().iter().map(|&(_, count)| count).sum();
-        count_key as f64 / total as f64
-    }
-
-    pub fn percentage(&self, key: T) -> f64 {
-        self._percentage(key, self.frequencies.lock().unwrap())
+        self.tx_update.send((self.update)(breakdown)).unwrap();
     }
 
     pub fn reset(&self) {
         let mut _freq = self.frequencies.lock().unwrap();
         *_freq = Frequencies::new();
 
-        self.tx_update
-            .send(MediatorUpdate::VoteSystemPercentageChange(None))
-            .unwrap();
+        self.send_breakdown(&_freq);
     }
 }
@@ -1,22 +1,26 @@
 use super::{Vote, VoteFunction, VoteSystemCreator, VoteSystemUpdate, VoteSystemUpdateSender};
+use crate::clock::SharedClock;
 use crate::command::Command;
+use crate::command_input::Input;
 
 struct _Anarchy {
     tx_decision: VoteSystemUpdateSender,
 }
 
 impl Vote for _Anarchy {
-    fn call(&self, c: Command) {
-        self.tx_decision
-            .send(VoteSystemUpdate::Decision(c))
-            .unwrap();
+    fn call(&self, input: Input) {
+        if let Some(button) = input.0.action() {
+            self.tx_decision
+                .send(VoteSystemUpdate::Decision(Command::Action(button)))
+                .unwrap();
+        }
     }
 }
 
 pub struct AnarchyCreator {}
 
 impl VoteSystemCreator for AnarchyCreator {
-    fn create(&self, tx_decision: VoteSystemUpdateSender) -> VoteFunction {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, _clock: SharedClock) -> VoteFunction {
         Box::new(_Anarchy { tx_decision })
     }
 }
@@ -0,0 +1,67 @@
+use super::democracy::{_Democracy, Tally};
+use super::{VoteFunction, VoteSystemCreator, VoteSystemUpdateSender};
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+
+use stats::Frequencies;
+use std::collections::HashSet;
+
+// Each message approves every button it lists, once.
+#[derive(Default)]
+struct ApprovalTally {
+    approvals: Frequencies<Command>,
+}
+
+impl Tally for ApprovalTally {
+    fn add(&mut self, c: Command) {
+        match c {
+            Command::Ballot(buttons) => {
+                let approved: HashSet<Button> = buttons.into_iter().collect();
+
+                for button in approved {
+                    self.approvals.add(Command::Action(button));
+                }
+            }
+            Command::Action(button) => self.approvals.add(Command::Action(button)),
+            _ => (),
+        }
+    }
+
+    fn winner(&self) -> Option<Command> {
+        self.approvals.winner()
+    }
+
+    fn results(&self) -> Frequencies<Command> {
+        self.approvals.clone()
+    }
+}
+
+pub struct ApprovalCreator {}
+
+impl VoteSystemCreator for ApprovalCreator {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_Democracy::<ApprovalTally>::new(tx_decision, clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approves_each_listed_button_once() {
+        use Button::*;
+
+        let mut tally = ApprovalTally::default();
+        tally.add(Command::Ballot(vec![A, B, A]));
+        tally.add(Command::Ballot(vec![B, Up]));
+        tally.add(Command::Action(B));
+        tally.add(Command::Join);
+
+        let results = tally.results();
+        assert_eq!(results.count(&Command::Action(A)), 1);
+        assert_eq!(results.count(&Command::Action(B)), 3);
+        assert_eq!(results.count(&Command::Action(Up)), 1);
+        assert_eq!(tally.winner(), Some(Command::Action(B)));
+    }
+}
@@ -1,7 +1,13 @@
-use super::{Vote, VoteFunction, VoteSystemCreator, VoteSystemUpdate, VoteSystemUpdateSender};
+use super::{
+    Vote, VoteFunction, VoteSystemCreator, VoteSystemSnapshot, VoteSystemUpdate,
+    VoteSystemUpdateSender,
+};
+use crate::clock::SharedClock;
 use crate::command::Command;
+use crate::command_input::Input;
 
 use stats::Frequencies;
+use std::mem;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
@@ -9,129 +15,154 @@ use std::thread;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
-struct _Democracy {
+// How the votes of a democracy round are counted.
+pub trait Tally: Default + Send + 'static {
+    fn add(&mut self, c: Command);
+    fn winner(&self) -> Option<Command>;
+    fn results(&self) -> Frequencies<Command>;
+}
+
+// Plurality: each button pressed is a vote for it.
+impl Tally for Frequencies<Command> {
+    fn add(&mut self, c: Command) {
+        if let Some(button) = c.action() {
+            Frequencies::add(self, Command::Action(button));
+        }
+    }
+
+    fn winner(&self) -> Option<Command> {
+        self.most_frequent()
+            .first()
+            .map(|&(command, _votes)| command.clone())
+    }
+
+    fn results(&self) -> Frequencies<Command> {
+        self.clone()
+    }
+}
+
+// The votes of the current round, both tallied and as they were cast.
+#[derive(Default)]
+struct Round<T: Tally> {
+    tally: T,
+    inputs: Vec<Input>,
+}
+
+impl<T: Tally> Round<T> {
+    fn add(&mut self, input: Input) {
+        self.tally.add(input.0.clone());
+        self.inputs.push(input);
+    }
+}
+
+pub(super) struct _Democracy<T: Tally> {
     stop_flag: Arc<AtomicBool>,
-    tx_decision: VoteSystemUpdateSender,
-    vote_map: Arc<Mutex<Frequencies<Command>>>,
+    vote_map: Arc<Mutex<Round<T>>>,
     last_decision: Arc<Mutex<Instant>>,
+    clock: SharedClock,
     handles: Vec<Option<JoinHandle<()>>>,
 }
 
-impl _Democracy {
+impl<T: Tally> _Democracy<T> {
     fn spawn_vote_counter(
         stop_flag: Arc<AtomicBool>,
         tx_decision: VoteSystemUpdateSender,
-        vote_map: Arc<Mutex<Frequencies<Command>>>,
+        vote_map: Arc<Mutex<Round<T>>>,
         last_decision: Arc<Mutex<Instant>>,
+        clock: SharedClock,
     ) -> JoinHandle<()> {
         thread::spawn(move || loop {
-            thread::park_timeout(Duration::from_secs(30));
+            clock.park_timeout(Duration::from_secs(30));
 
             if stop_flag.load(Ordering::SeqCst) {
                 break;
             }
 
             {
-                let mut _freq = vote_map.lock().unwrap();
-
-                match _freq.most_frequent().first() {
-                    Some(&(command, _votes)) => {
-                        tx_decision
-                            .send(VoteSystemUpdate::Decision(command.clone()))
-                            .unwrap();
-                    }
-                    None => (),
-                }
-
-                *_freq = Frequencies::new();
-            }
-
-            *last_decision.lock().unwrap() = Instant::now();
-        })
-    }
-
-    fn spawn_partial_results_sender(
-        stop_flag: Arc<AtomicBool>,
-        tx_decision: VoteSystemUpdateSender,
-        vote_map: Arc<Mutex<Frequencies<Command>>>,
-        last_decision: Arc<Mutex<Instant>>,
-    ) -> JoinHandle<()> {
-        thread::spawn(move || {
-            loop {
-                thread::park_timeout(Duration::from_secs(1));
+                let mut _round = vote_map.lock().unwrap();
 
-                if stop_flag.load(Ordering::SeqCst) {
-                    break;
+                if let Some(command) = _round.tally.winner() {
+                    tx_decision
+                        .send(VoteSystemUpdate::Decision(command))
+                        .unwrap();
                 }
 
-                let mut _vote = vote_map.lock().unwrap();
-
-                let time_remaining =
-                    Duration::from_secs(30)
-                    .checked_sub(last_decision.lock().unwrap().elapsed())
-                    .unwrap_or(Duration::from_secs(0));
-
-                // ye
-                tx_decision
-                    .send(VoteSystemUpdate::DemocracyPartialResults(
-                        time_remaining.as_secs(),
-                        _vote.clone(),
-                    ))
-                    .unwrap();
+                *_round = Round::default();
             }
+
+            *last_decision.lock().unwrap() = clock.now();
         })
     }
 
-    fn new(tx_decision: VoteSystemUpdateSender) -> Self {
-        let vote_map = Arc::new(Mutex::new(Frequencies::new()));
-        let last_decision = Arc::new(Mutex::new(Instant::now()));
+    pub(super) fn new(tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> Self {
+        let vote_map = Arc::new(Mutex::new(Round::default()));
+        let last_decision = Arc::new(Mutex::new(clock.now()));
         let stop_flag = Arc::new(AtomicBool::new(false));
         let mut handles = Vec::new();
 
         handles.push(Some(Self::spawn_vote_counter(
             stop_flag.clone(),
-            tx_decision.clone(),
-            vote_map.clone(),
-            last_decision.clone(),
-        )));
-        handles.push(Some(Self::spawn_partial_results_sender(
-            stop_flag.clone(),
-            tx_decision.clone(),
+            tx_decision,
             vote_map.clone(),
             last_decision.clone(),
+            clock.clone(),
         )));
 
         Self {
             stop_flag,
-            tx_decision,
             vote_map,
             last_decision,
+            clock,
             handles,
         }
     }
+
+    fn time_remaining(&self) -> Duration {
+        // Sometimes elapsed() can be >30s.
+        Duration::from_secs(30)
+            .checked_sub(self.clock.elapsed(*self.last_decision.lock().unwrap()))
+            .unwrap_or(Duration::from_secs(0))
+    }
 }
 
-impl Vote for _Democracy {
-    fn call(&self, c: Command) {
+impl<T: Tally> Vote for _Democracy<T> {
+    fn call(&self, input: Input) {
+        self.vote_map.lock().unwrap().add(input);
+    }
+
+    // Votes from another democracy-like system count towards the current round.
+    fn on_activate(&self, pending: Vec<Input>) -> bool {
         let mut _vote = self.vote_map.lock().unwrap();
-        _vote.add(c);
 
-        // Sometimes elapsed() can be >30s.
-        let time_remaining = Duration::from_secs(30)
-            .checked_sub(self.last_decision.lock().unwrap().elapsed())
-            .unwrap_or(Duration::from_secs(0));
-
-        // ye
-        self.tx_decision
-            .send(VoteSystemUpdate::DemocracyPartialResults(
-                time_remaining.as_secs(),
-                _vote.clone(),
-            ))
-            .unwrap();
+        for input in pending {
+            _vote.add(input);
+        }
+
+        true
+    }
+
+    fn on_deactivate(&self) -> Vec<Input> {
+        mem::take(&mut self.vote_map.lock().unwrap().inputs)
+    }
+
+    fn snapshot(&self) -> VoteSystemSnapshot {
+        let _vote = self.vote_map.lock().unwrap();
+
+        VoteSystemSnapshot::Democracy {
+            secs_remaining: self.time_remaining().as_secs(),
+            results: _vote
+                .tally
+                .results()
+                .most_frequent()
+                .iter()
+                .map(|&(command, votes)| (command.clone(), votes))
+                .collect(),
+            ballots: _vote.inputs.len(),
+        }
     }
 }
 
-impl Drop for _Democracy {
+impl<T: Tally> Drop for _Democracy<T> {
     fn drop(&mut self) {
         self.stop_flag.store(true, Ordering::SeqCst);
 
@@ -155,7 +186,141 @@ impl Drop for _Democracy {
 pub struct DemocracyCreator {}
 
 impl VoteSystemCreator for DemocracyCreator {
-    fn create(&self, tx_decision: VoteSystemUpdateSender) -> VoteFunction {
-        Box::new(_Democracy::new(tx_decision))
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_Democracy::<Frequencies<Command>>::new(tx_decision, clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::command::Button;
+    use crate::command_input::Chatter;
+
+    use std::sync::mpsc::{channel, Receiver};
+
+    // The round counter.
+    const THREADS: usize = 1;
+
+    fn democracy(clock: &Arc<ManualClock>) -> (VoteFunction, Receiver<VoteSystemUpdate>) {
+        let (tx, rx) = channel();
+        let democracy = DemocracyCreator {}.create(tx, clock.clone());
+        clock.wait_for_sleepers(THREADS);
+
+        (democracy, rx)
+    }
+
+    fn vote(democracy: &VoteFunction, button: Button) {
+        democracy.call(Input(Command::Action(button), Chatter::new("user")));
+    }
+
+    // Advances to the end of the round, and waits for the threads to be done with it.
+    fn end_round(clock: &ManualClock) {
+        clock.advance(Duration::from_secs(30));
+        clock.wait_for_sleepers(THREADS);
+    }
+
+    fn decisions(rx: &Receiver<VoteSystemUpdate>) -> Vec<Command> {
+        rx.try_iter()
+            .map(|VoteSystemUpdate::Decision(command)| command)
+            .collect()
+    }
+
+    #[test]
+    fn decides_most_voted_command_at_end_of_round() {
+        let clock = Arc::new(ManualClock::new());
+        let (democracy, rx) = democracy(&clock);
+
+        vote(&democracy, Button::A);
+        vote(&democracy, Button::Up);
+        vote(&democracy, Button::Up);
+
+        clock.advance(Duration::from_secs(29));
+        clock.wait_for_sleepers(THREADS);
+        assert_eq!(decisions(&rx), vec![]);
+
+        clock.advance(Duration::from_secs(1));
+        clock.wait_for_sleepers(THREADS);
+        assert_eq!(decisions(&rx), vec![Command::Action(Button::Up)]);
+    }
+
+    #[test]
+    fn rounds_start_with_no_votes() {
+        let clock = Arc::new(ManualClock::new());
+        let (democracy, rx) = democracy(&clock);
+
+        vote(&democracy, Button::Up);
+        vote(&democracy, Button::Up);
+        end_round(&clock);
+
+        vote(&democracy, Button::B);
+        end_round(&clock);
+
+        end_round(&clock);
+
+        assert_eq!(
+            decisions(&rx),
+            vec![Command::Action(Button::Up), Command::Action(Button::B)]
+        );
+    }
+
+    #[test]
+    fn partial_results_count_down_to_end_of_round() {
+        let clock = Arc::new(ManualClock::new());
+        let (democracy, _rx) = democracy(&clock);
+
+        clock.advance(Duration::from_secs(10));
+        clock.wait_for_sleepers(THREADS);
+        vote(&democracy, Button::Start);
+
+        match democracy.snapshot() {
+            VoteSystemSnapshot::Democracy {
+                secs_remaining,
+                results,
+                ..
+            } => {
+                assert_eq!(secs_remaining, 20);
+                assert_eq!(results, vec![(Command::Action(Button::Start), 1)]);
+            }
+            snapshot => panic!("unexpected snapshot {:?}", snapshot),
+        }
+    }
+
+    #[test]
+    fn pending_votes_carry_over_to_next_democracy() {
+        let clock = Arc::new(ManualClock::new());
+        let (previous, _previous_rx) = democracy(&clock);
+
+        vote(&previous, Button::Left);
+        vote(&previous, Button::Left);
+        vote(&previous, Button::Right);
+
+        let pending = previous.on_deactivate();
+        drop(previous);
+        assert_eq!(pending.len(), 3);
+
+        let (next, rx) = democracy(&clock);
+        assert!(next.on_activate(pending));
+
+        match next.snapshot() {
+            VoteSystemSnapshot::Democracy { ballots, .. } => assert_eq!(ballots, 3),
+            snapshot => panic!("unexpected snapshot {:?}", snapshot),
+        }
+
+        end_round(&clock);
+        assert_eq!(decisions(&rx), vec![Command::Action(Button::Left)]);
+    }
+
+    #[test]
+    fn drop_stops_threads_without_deciding() {
+        let clock = Arc::new(ManualClock::new());
+        let (democracy, rx) = democracy(&clock);
+
+        vote(&democracy, Button::Up);
+        drop(democracy);
+
+        // Every sender is gone once the threads are joined.
+        assert_eq!(rx.iter().count(), 0);
     }
 }
@@ -0,0 +1,145 @@
+use super::{Vote, VoteFunction, VoteSystemCreator, VoteSystemUpdate, VoteSystemUpdateSender};
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+use crate::command_input::Input;
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use stats::Frequencies;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+    Random,
+    Plurality,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct FilteredAnarchyConfig {
+    pub tick_ms: u64,
+    pub sampling: Sampling,
+}
+
+impl Default for FilteredAnarchyConfig {
+    fn default() -> Self {
+        FilteredAnarchyConfig {
+            tick_ms: 250,
+            sampling: Sampling::Random,
+        }
+    }
+}
+
+impl FilteredAnarchyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tick_ms == 0 {
+            return Err("filtered_anarchy: tick_ms can't be 0".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+struct _FilteredAnarchy {
+    stop_flag: Arc<AtomicBool>,
+    pending: Arc<Mutex<Vec<Button>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl _FilteredAnarchy {
+    fn sample(pending: Vec<Button>, sampling: Sampling) -> Option<Button> {
+        match sampling {
+            Sampling::Random => pending.choose(&mut rand::thread_rng()).cloned(),
+            Sampling::Plurality => {
+                let freq: Frequencies<Button> = pending.into_iter().collect();
+
+                freq.most_frequent().first().map(|&(&button, _)| button)
+            }
+        }
+    }
+
+    fn spawn_sampler(
+        stop_flag: Arc<AtomicBool>,
+        tx_decision: VoteSystemUpdateSender,
+        pending: Arc<Mutex<Vec<Button>>>,
+        config: FilteredAnarchyConfig,
+        clock: SharedClock,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || loop {
+            clock.park_timeout(Duration::from_millis(config.tick_ms));
+
+            if stop_flag.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let window = std::mem::take(&mut *pending.lock().unwrap());
+
+            if let Some(button) = Self::sample(window, config.sampling) {
+                tx_decision
+                    .send(VoteSystemUpdate::Decision(Command::Action(button)))
+                    .unwrap();
+            }
+        })
+    }
+
+    fn new(
+        tx_decision: VoteSystemUpdateSender,
+        config: FilteredAnarchyConfig,
+        clock: SharedClock,
+    ) -> Self {
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let stop_flag = Arc::new(AtomicBool::new(false));
+
+        let handle = Self::spawn_sampler(
+            stop_flag.clone(),
+            tx_decision,
+            pending.clone(),
+            config,
+            clock,
+        );
+
+        Self {
+            stop_flag,
+            pending,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Vote for _FilteredAnarchy {
+    fn call(&self, input: Input) {
+        if let Some(button) = input.0.action() {
+            self.pending.lock().unwrap().push(button);
+        }
+    }
+}
+
+impl Drop for _FilteredAnarchy {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+
+        if let Some(h) = self.handle.take() {
+            h.thread().unpark();
+            h.join().unwrap();
+        }
+    }
+}
+
+pub struct FilteredAnarchyCreator {
+    pub config: FilteredAnarchyConfig,
+}
+
+impl VoteSystemCreator for FilteredAnarchyCreator {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_FilteredAnarchy::new(
+            tx_decision,
+            self.config.clone(),
+            clock,
+        ))
+    }
+}
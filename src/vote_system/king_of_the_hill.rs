@@ -0,0 +1,246 @@
+use super::{
+    Vote, VoteFunction, VoteSystemCreator, VoteSystemSnapshot, VoteSystemUpdate,
+    VoteSystemUpdateSender,
+};
+use crate::clock::SharedClock;
+use crate::command::Command;
+use crate::command_input::{Chatter, Input, User};
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct KingOfTheHillConfig {
+    pub slot_secs: u64,
+    pub idle_secs: u64,
+}
+
+impl Default for KingOfTheHillConfig {
+    fn default() -> Self {
+        KingOfTheHillConfig {
+            slot_secs: 60,
+            idle_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KingOfTheHillState {
+    pub player: Option<User>,
+    pub queue: Vec<User>,
+    pub secs_remaining: u64,
+}
+
+struct Hill {
+    player: Option<User>,
+    queue: VecDeque<User>,
+    slot_start: Instant,
+    last_input: Instant,
+    clock: SharedClock,
+}
+
+impl Hill {
+    fn join(&mut self, user: User) {
+        if self.player.as_ref() != Some(&user) && !self.queue.contains(&user) {
+            self.queue.push_back(user);
+        }
+
+        if self.player.is_none() {
+            self.next_player();
+        }
+    }
+
+    fn next_player(&mut self) {
+        self.player = self.queue.pop_front();
+        self.slot_start = self.clock.now();
+        self.last_input = self.clock.now();
+    }
+
+    // Hands the controller over when the slot expires, or as soon as the player goes idle.
+    fn rotate(&mut self, config: &KingOfTheHillConfig) {
+        if self.player.is_none() {
+            return;
+        }
+
+        let idle = self.clock.elapsed(self.last_input) >= Duration::from_secs(config.idle_secs);
+        let expired = self.clock.elapsed(self.slot_start) >= Duration::from_secs(config.slot_secs);
+
+        if idle || (expired && !self.queue.is_empty()) {
+            self.next_player();
+        } else if expired {
+            // Nobody is waiting, so the current player keeps playing.
+            self.slot_start = self.clock.now();
+        }
+    }
+
+    fn state(&self, config: &KingOfTheHillConfig) -> KingOfTheHillState {
+        let secs_remaining = match self.player {
+            Some(_) => Duration::from_secs(config.slot_secs)
+                .checked_sub(self.clock.elapsed(self.slot_start))
+                .unwrap_or(Duration::from_secs(0))
+                .as_secs(),
+            None => 0,
+        };
+
+        KingOfTheHillState {
+            player: self.player.clone(),
+            queue: self.queue.iter().cloned().collect(),
+            secs_remaining,
+        }
+    }
+}
+
+struct _KingOfTheHill {
+    tx_decision: VoteSystemUpdateSender,
+    hill: Mutex<Hill>,
+    config: KingOfTheHillConfig,
+}
+
+impl _KingOfTheHill {
+    fn new(
+        tx_decision: VoteSystemUpdateSender,
+        config: KingOfTheHillConfig,
+        clock: SharedClock,
+    ) -> Self {
+        let hill = Mutex::new(Hill {
+            player: None,
+            queue: VecDeque::new(),
+            slot_start: clock.now(),
+            last_input: clock.now(),
+            clock,
+        });
+
+        Self {
+            tx_decision,
+            hill,
+            config,
+        }
+    }
+}
+
+impl Vote for _KingOfTheHill {
+    fn call(&self, input: Input) {
+        let Input(cmd, Chatter { name: user, .. }) = input;
+        let mut _hill = self.hill.lock().unwrap();
+
+        if let Command::Join = cmd {
+            _hill.join(user);
+        } else if _hill.player.as_ref() == Some(&user) {
+            if let Some(button) = cmd.action() {
+                _hill.last_input = _hill.clock.now();
+
+                self.tx_decision
+                    .send(VoteSystemUpdate::Decision(Command::Action(button)))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn tick(&self) {
+        self.hill.lock().unwrap().rotate(&self.config);
+    }
+
+    fn snapshot(&self) -> VoteSystemSnapshot {
+        VoteSystemSnapshot::KingOfTheHill(self.hill.lock().unwrap().state(&self.config))
+    }
+}
+
+pub struct KingOfTheHillCreator {
+    pub config: KingOfTheHillConfig,
+}
+
+impl VoteSystemCreator for KingOfTheHillCreator {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_KingOfTheHill::new(tx_decision, self.config.clone(), clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::command::Button;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    fn decisions(rx: &std::sync::mpsc::Receiver<VoteSystemUpdate>) -> Vec<Command> {
+        rx.try_iter()
+            .map(|VoteSystemUpdate::Decision(command)| command)
+            .collect()
+    }
+
+    #[test]
+    fn first_to_join_plays_and_only_their_inputs_count() {
+        let clock = Arc::new(ManualClock::new());
+        let (tx, rx) = channel();
+        let koth = _KingOfTheHill::new(tx, KingOfTheHillConfig::default(), clock.clone());
+        let press = |user: &str, button| {
+            koth.call(Input(Command::Action(button), Chatter::new(user)));
+        };
+
+        press("alice", Button::A);
+        for user in &["alice", "bob", "alice"] {
+            koth.call(Input(Command::Join, Chatter::new(user)));
+        }
+        press("bob", Button::B);
+        press("alice", Button::Up);
+
+        assert_eq!(decisions(&rx), vec![Command::Action(Button::Up)]);
+        let state = koth.hill.lock().unwrap().state(&koth.config);
+        assert_eq!(state.player, Some("alice".to_owned()));
+        assert_eq!(state.queue, vec!["bob".to_owned()]);
+    }
+
+    #[test]
+    fn rotates_when_the_slot_expires_or_the_player_idles() {
+        let clock = Arc::new(ManualClock::new());
+        let config = KingOfTheHillConfig {
+            slot_secs: 60,
+            idle_secs: 10,
+        };
+        let mut hill = Hill {
+            player: None,
+            queue: VecDeque::new(),
+            slot_start: clock.now(),
+            last_input: clock.now(),
+            clock: clock.clone(),
+        };
+        let player = |hill: &Hill| hill.player.clone().unwrap_or_default();
+
+        for user in &["alice", "bob", "carol"] {
+            hill.join(user.to_string());
+        }
+
+        // Alice keeps playing until the slot runs out.
+        for _ in 0..12 {
+            clock.advance(Duration::from_secs(5));
+            hill.last_input = clock.now();
+            hill.rotate(&config);
+        }
+        assert_eq!(player(&hill), "bob");
+
+        // Bob never presses anything.
+        clock.advance(Duration::from_secs(9));
+        hill.rotate(&config);
+        assert_eq!(player(&hill), "bob");
+        clock.advance(Duration::from_secs(1));
+        hill.rotate(&config);
+        assert_eq!(player(&hill), "carol");
+
+        // With nobody waiting, Carol gets another slot.
+        for _ in 0..12 {
+            clock.advance(Duration::from_secs(5));
+            hill.last_input = clock.now();
+            hill.rotate(&config);
+        }
+        assert_eq!(player(&hill), "carol");
+        assert_eq!(hill.state(&config).secs_remaining, 60);
+
+        clock.advance(Duration::from_secs(10));
+        hill.rotate(&config);
+        assert_eq!(hill.player, None);
+    }
+}
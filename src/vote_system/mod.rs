@@ -1,25 +1,59 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::clock::SharedClock;
 use crate::command::Command;
-use stats::Frequencies;
+use crate::command_input::Input;
+use std::collections::HashMap;
 use std::sync::mpsc::{Receiver, Sender};
 
 pub trait Vote: Send {
-    fn call(&self, c: Command);
+    fn call(&self, input: Input);
+
+    // Called when the system replaces another one, with the votes that were still pending
+    // there. Returns whether they were taken over.
+    fn on_activate(&self, _pending: Vec<Input>) -> bool {
+        false
+    }
+
+    // Called before the system is replaced, returns the votes that are still pending.
+    fn on_deactivate(&self) -> Vec<Input> {
+        Vec::new()
+    }
+
+    // Called by the mediator every second while the system is active, right before taking
+    // a snapshot of it.
+    fn tick(&self) {}
+
+    fn snapshot(&self) -> VoteSystemSnapshot {
+        VoteSystemSnapshot::Stateless
+    }
 }
 
 pub type VoteFunction = Box<Vote>;
 
 pub enum VoteSystemUpdate {
     Decision(Command),
-    DemocracyPartialResults(u64, Frequencies<Command>),
+}
+
+// The state of the active vote system, as shown by the renderer.
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VoteSystemSnapshot {
+    Stateless,
+    Democracy {
+        secs_remaining: u64,
+        results: Vec<(Command, u64)>,
+        ballots: usize,
+    },
+    KingOfTheHill(KingOfTheHillState),
+    Teams(TeamsState),
 }
 
 pub type VoteSystemUpdateReceiver = Receiver<VoteSystemUpdate>;
 pub type VoteSystemUpdateSender = Sender<VoteSystemUpdate>;
 
-pub trait VoteSystemCreator {
-    fn create(&self, d: VoteSystemUpdateSender) -> VoteFunction;
+pub trait VoteSystemCreator: Send + Sync {
+    fn create(&self, d: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction;
 }
 
 pub mod anarchy;
@@ -28,17 +62,49 @@ pub use anarchy::AnarchyCreator;
 pub mod democracy;
 pub use democracy::DemocracyCreator;
 
-#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy, Hash)]
-pub enum VoteSystem {
-    Anarchy,
-    Democracy,
+pub mod filtered_anarchy;
+pub use filtered_anarchy::{FilteredAnarchyConfig, FilteredAnarchyCreator};
+
+pub mod approval;
+pub use approval::ApprovalCreator;
+
+pub mod ranked_choice;
+pub use ranked_choice::RankedChoiceCreator;
+
+pub mod king_of_the_hill;
+pub use king_of_the_hill::{KingOfTheHillConfig, KingOfTheHillCreator, KingOfTheHillState};
+
+pub mod teams;
+pub use teams::{TeamsConfig, TeamsCreator, TeamsState};
+
+pub mod registry;
+pub use registry::VoteSystemRegistry;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct VoteSystemsConfig {
+    pub filtered_anarchy: FilteredAnarchyConfig,
+    pub king_of_the_hill: KingOfTheHillConfig,
+    pub teams: TeamsConfig,
+    // Extra chat keywords, mapped to the name of a registered vote system.
+    pub keywords: HashMap<String, String>,
+}
+
+impl VoteSystemsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.filtered_anarchy.validate()
+    }
 }
 
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Hash)]
+pub struct VoteSystem(String);
+
 impl VoteSystem {
-    pub fn creator(&self) -> Box<VoteSystemCreator> {
-        match self {
-            VoteSystem::Anarchy => Box::new(AnarchyCreator {}),
-            VoteSystem::Democracy => Box::new(DemocracyCreator {}),
-        }
+    pub fn new(name: &str) -> Self {
+        VoteSystem(name.to_owned())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
     }
 }
@@ -0,0 +1,134 @@
+use super::democracy::{_Democracy, Tally};
+use super::{VoteFunction, VoteSystemCreator, VoteSystemUpdateSender};
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+
+use stats::Frequencies;
+use std::collections::HashSet;
+
+// Instant-runoff: every message is a ballot of buttons in order of preference.
+#[derive(Default)]
+struct RankedChoiceTally {
+    ballots: Vec<Vec<Button>>,
+}
+
+impl RankedChoiceTally {
+    fn first_preferences(&self, eliminated: &HashSet<Button>) -> Frequencies<Button> {
+        self.ballots
+            .iter()
+            .filter_map(|ballot| ballot.iter().find(|b| !eliminated.contains(b)))
+            .cloned()
+            .collect()
+    }
+
+    // Eliminates the least voted button until one has a majority of the remaining ballots.
+    // Ties for last are broken by button order, the later button going out first.
+    fn final_round(&self) -> Frequencies<Button> {
+        let mut eliminated = HashSet::new();
+
+        loop {
+            let round = self.first_preferences(&eliminated);
+            let standings = round.most_frequent();
+            let total: u64 = standings.iter().map(|&(_, count)| count).sum();
+
+            match (standings.first(), standings.last()) {
+                (Some(&(_, top)), Some(&(_, fewest))) if top * 2 <= total => {
+                    let last = standings
+                        .iter()
+                        .filter(|&&(_, count)| count == fewest)
+                        .map(|&(&button, _)| button)
+                        .max()
+                        .unwrap();
+
+                    eliminated.insert(last);
+                }
+                _ => return round,
+            }
+        }
+    }
+}
+
+impl Tally for RankedChoiceTally {
+    fn add(&mut self, c: Command) {
+        match c {
+            Command::Ballot(buttons) => {
+                let mut seen = HashSet::new();
+                let ballot = buttons.into_iter().filter(|b| seen.insert(*b)).collect();
+
+                self.ballots.push(ballot);
+            }
+            Command::Action(button) => self.ballots.push(vec![button]),
+            _ => (),
+        }
+    }
+
+    fn winner(&self) -> Option<Command> {
+        self.final_round()
+            .most_frequent()
+            .first()
+            .map(|&(&button, _)| Command::Action(button))
+    }
+
+    fn results(&self) -> Frequencies<Command> {
+        let mut results = Frequencies::new();
+
+        for (&button, count) in self.final_round().most_frequent() {
+            for _ in 0..count {
+                results.add(Command::Action(button));
+            }
+        }
+
+        results
+    }
+}
+
+pub struct RankedChoiceCreator {}
+
+impl VoteSystemCreator for RankedChoiceCreator {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_Democracy::<RankedChoiceTally>::new(tx_decision, clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(ballots: &[&[Button]]) -> RankedChoiceTally {
+        let mut tally = RankedChoiceTally::default();
+        for ballot in ballots {
+            tally.add(Command::Ballot(ballot.to_vec()));
+        }
+
+        tally
+    }
+
+    #[test]
+    fn transfers_eliminated_ballots_to_the_next_preference() {
+        use Button::*;
+
+        let tally = tally(&[&[A], &[A], &[B, A], &[B, A], &[Up, Up, B]]);
+        let results = tally.results();
+
+        assert_eq!(tally.winner(), Some(Command::Action(B)));
+        assert_eq!(results.count(&Command::Action(B)), 3);
+        assert_eq!(results.count(&Command::Action(A)), 2);
+        assert_eq!(results.count(&Command::Action(Up)), 0);
+    }
+
+    #[test]
+    fn breaks_ties_for_last_by_button_order() {
+        use Button::*;
+
+        for _ in 0..20 {
+            assert_eq!(
+                tally(&[&[A], &[B], &[Start]]).winner(),
+                Some(Command::Action(A))
+            );
+            assert_eq!(
+                tally(&[&[Start, B], &[B], &[A], &[A, B]]).winner(),
+                Some(Command::Action(A))
+            );
+        }
+    }
+}
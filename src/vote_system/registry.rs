@@ -0,0 +1,147 @@
+use super::{
+    AnarchyCreator, ApprovalCreator, DemocracyCreator, FilteredAnarchyCreator,
+    KingOfTheHillCreator, RankedChoiceCreator, TeamsCreator, VoteFunction, VoteSystem,
+    VoteSystemCreator, VoteSystemUpdateSender, VoteSystemsConfig,
+};
+
+use crate::clock::SharedClock;
+use std::collections::HashMap;
+
+struct RegisteredVoteSystem {
+    system: VoteSystem,
+    creator: Box<dyn VoteSystemCreator>,
+}
+
+pub struct VoteSystemRegistry {
+    systems: Vec<RegisteredVoteSystem>,
+    keywords: HashMap<String, VoteSystem>,
+}
+
+impl VoteSystemRegistry {
+    pub fn new() -> Self {
+        VoteSystemRegistry {
+            systems: Vec::new(),
+            keywords: HashMap::new(),
+        }
+    }
+
+    pub fn with_builtin_systems(config: &VoteSystemsConfig) -> Self {
+        let mut registry = Self::new();
+
+        registry.register("Anarchy", AnarchyCreator {}, &["anarchy"]);
+        registry.register("Democracy", DemocracyCreator {}, &["democracy"]);
+        registry.register(
+            "FilteredAnarchy",
+            FilteredAnarchyCreator {
+                config: config.filtered_anarchy.clone(),
+            },
+            &["filtered"],
+        );
+        registry.register("Approval", ApprovalCreator {}, &["approval"]);
+        registry.register("RankedChoice", RankedChoiceCreator {}, &["ranked"]);
+        registry.register(
+            "KingOfTheHill",
+            KingOfTheHillCreator {
+                config: config.king_of_the_hill.clone(),
+            },
+            &["koth"],
+        );
+        registry.register(
+            "Teams",
+            TeamsCreator {
+                config: config.teams.clone(),
+            },
+            &["teams"],
+        );
+
+        for (keyword, name) in &config.keywords {
+            match registry.get(name) {
+                Some(system) => registry.add_keyword(keyword, system),
+                None => println!(
+                    "VoteSystemRegistry: unknown vote system {} for keyword {}",
+                    name, keyword
+                ),
+            }
+        }
+
+        registry
+    }
+
+    pub fn register<C>(&mut self, name: &str, creator: C, keywords: &[&str]) -> VoteSystem
+    where
+        C: VoteSystemCreator + 'static,
+    {
+        let system = VoteSystem::new(name);
+
+        self.systems
+            .retain(|registered| registered.system != system);
+        self.systems.push(RegisteredVoteSystem {
+            system: system.clone(),
+            creator: Box::new(creator),
+        });
+
+        for keyword in keywords {
+            self.add_keyword(keyword, system.clone());
+        }
+
+        system
+    }
+
+    pub fn add_keyword(&mut self, keyword: &str, system: VoteSystem) {
+        self.keywords.insert(keyword.to_lowercase(), system);
+    }
+
+    pub fn get(&self, name: &str) -> Option<VoteSystem> {
+        self.systems
+            .iter()
+            .find(|registered| registered.system.name() == name)
+            .map(|registered| registered.system.clone())
+    }
+
+    pub fn by_keyword(&self, keyword: &str) -> Option<VoteSystem> {
+        self.keywords.get(&keyword.to_lowercase()).cloned()
+    }
+
+    pub fn systems(&self) -> Vec<VoteSystem> {
+        self.systems
+            .iter()
+            .map(|registered| registered.system.clone())
+            .collect()
+    }
+
+    pub fn create(
+        &self,
+        system: &VoteSystem,
+        tx_decision: VoteSystemUpdateSender,
+        clock: SharedClock,
+    ) -> Option<VoteFunction> {
+        self.systems
+            .iter()
+            .find(|registered| registered.system == *system)
+            .map(|registered| registered.creator.create(tx_decision, clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    #[test]
+    fn finds_keywords_in_any_case_and_only_creates_registered_systems() {
+        let registry = VoteSystemRegistry::with_builtin_systems(&Default::default());
+
+        assert_eq!(
+            registry.by_keyword("Anarchy"),
+            Some(VoteSystem::new("Anarchy"))
+        );
+
+        let (tx, _rx) = channel();
+        let clock: SharedClock = Arc::new(SystemClock {});
+        assert!(registry
+            .create(&VoteSystem::new("Monarchy"), tx, clock)
+            .is_none());
+    }
+}
@@ -0,0 +1,361 @@
+use super::{
+    Vote, VoteFunction, VoteSystemCreator, VoteSystemSnapshot, VoteSystemUpdate,
+    VoteSystemUpdateSender,
+};
+use crate::clock::SharedClock;
+use crate::command::{Button, Command};
+use crate::command_input::{Chatter, Input, User};
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamsMode {
+    // Each team controls its own subset of buttons.
+    Buttons,
+    // Teams take turns controlling every button.
+    Turns,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TeamConfig {
+    pub name: String,
+    // Unknown buttons are refused when reading the settings.
+    #[serde(default)]
+    pub buttons: Vec<Button>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct TeamsConfig {
+    pub mode: TeamsMode,
+    // How long turns last, and how often the busiest team scores in button mode.
+    pub turn_secs: u64,
+    pub teams: Vec<TeamConfig>,
+}
+
+impl Default for TeamsConfig {
+    fn default() -> Self {
+        use Button::*;
+
+        let team = |name: &str, buttons: &[Button]| TeamConfig {
+            name: name.to_owned(),
+            buttons: buttons.to_vec(),
+        };
+
+        TeamsConfig {
+            mode: TeamsMode::Buttons,
+            turn_secs: 15,
+            teams: vec![
+                team("red", &[Up, Down, Left, Right]),
+                team("blue", &[A, B, Select, Start, L, R]),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TeamState {
+    pub name: String,
+    pub buttons: Vec<Button>,
+    pub members: Vec<User>,
+    // Everything the members sent, pressed or not.
+    pub inputs: u64,
+    // Rounds in which the team pressed the most buttons.
+    pub score: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TeamsState {
+    pub teams: Vec<TeamState>,
+    pub active_team: Option<String>,
+    pub secs_remaining: u64,
+}
+
+struct Teams {
+    teams: Vec<TeamState>,
+    membership: HashMap<User, usize>,
+    active_team: usize,
+    turn_start: Instant,
+    // Buttons each team pressed this round. A round is a turn in button mode, and every
+    // team having a turn in turn mode.
+    pressed: Vec<u64>,
+    clock: SharedClock,
+}
+
+impl Teams {
+    fn new(config: &TeamsConfig, clock: SharedClock) -> Self {
+        let teams = config
+            .teams
+            .iter()
+            .map(|team| TeamState {
+                name: team.name.clone(),
+                buttons: team.buttons.clone(),
+                members: Vec::new(),
+                inputs: 0,
+                score: 0,
+            })
+            .collect();
+
+        Teams {
+            teams,
+            membership: HashMap::new(),
+            active_team: 0,
+            turn_start: clock.now(),
+            pressed: vec![0; config.teams.len()],
+            clock,
+        }
+    }
+
+    fn assign(&mut self, user: &User, team: usize) {
+        if let Some(previous) = self.membership.insert(user.clone(), team) {
+            self.teams[previous].members.retain(|member| member != user);
+        }
+
+        self.teams[team].members.push(user.clone());
+    }
+
+    // Chatters that didn't pick a team are assigned to one by the hash of their name.
+    fn team_of(&mut self, user: &User) -> usize {
+        if let Some(&team) = self.membership.get(user) {
+            return team;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        user.hash(&mut hasher);
+        let team = (hasher.finish() % self.teams.len() as u64) as usize;
+
+        self.assign(user, team);
+        team
+    }
+
+    fn join(&mut self, user: &User, name: &str) {
+        if let Some(team) = self
+            .teams
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name))
+        {
+            self.assign(user, team);
+        }
+    }
+
+    fn can_press(&self, team: usize, button: Button, mode: TeamsMode) -> bool {
+        match mode {
+            TeamsMode::Buttons => self.teams[team].buttons.contains(&button),
+            TeamsMode::Turns => team == self.active_team,
+        }
+    }
+
+    // Whether the team's member could press the button, counting it.
+    fn press(&mut self, user: &User, button: Button, mode: TeamsMode) -> bool {
+        let team = self.team_of(user);
+        self.teams[team].inputs += 1;
+
+        let pressed = self.can_press(team, button, mode);
+        if pressed {
+            self.pressed[team] += 1;
+        }
+
+        pressed
+    }
+
+    fn rotate(&mut self, config: &TeamsConfig) {
+        if self.teams.is_empty()
+            || self.clock.elapsed(self.turn_start) < Duration::from_secs(config.turn_secs)
+        {
+            return;
+        }
+
+        self.turn_start = self.clock.now();
+        let round_over = match config.mode {
+            TeamsMode::Buttons => true,
+            TeamsMode::Turns => {
+                self.active_team = (self.active_team + 1) % self.teams.len();
+                self.active_team == 0
+            }
+        };
+
+        if round_over {
+            self.score_round();
+        }
+    }
+
+    // A point for the team that pressed the most, unless it's a tie.
+    fn score_round(&mut self) {
+        let most = self.pressed.iter().copied().max().unwrap_or(0);
+        let leaders: Vec<usize> = (0..self.pressed.len())
+            .filter(|&team| self.pressed[team] == most)
+            .collect();
+
+        if most > 0 && leaders.len() == 1 {
+            self.teams[leaders[0]].score += 1;
+        }
+
+        self.pressed = vec![0; self.teams.len()];
+    }
+
+    fn state(&self, config: &TeamsConfig) -> TeamsState {
+        match config.mode {
+            TeamsMode::Buttons => TeamsState {
+                teams: self.teams.clone(),
+                active_team: None,
+                secs_remaining: 0,
+            },
+            TeamsMode::Turns => TeamsState {
+                teams: self.teams.clone(),
+                active_team: self.teams.get(self.active_team).map(|t| t.name.clone()),
+                secs_remaining: Duration::from_secs(config.turn_secs)
+                    .checked_sub(self.clock.elapsed(self.turn_start))
+                    .unwrap_or(Duration::from_secs(0))
+                    .as_secs(),
+            },
+        }
+    }
+}
+
+struct _Teams {
+    tx_decision: VoteSystemUpdateSender,
+    teams: Mutex<Teams>,
+    config: TeamsConfig,
+}
+
+impl _Teams {
+    fn new(tx_decision: VoteSystemUpdateSender, config: TeamsConfig, clock: SharedClock) -> Self {
+        let teams = Mutex::new(Teams::new(&config, clock));
+
+        Self {
+            tx_decision,
+            teams,
+            config,
+        }
+    }
+}
+
+impl Vote for _Teams {
+    fn call(&self, input: Input) {
+        let Input(cmd, Chatter { name: user, .. }) = input;
+        let mut _teams = self.teams.lock().unwrap();
+
+        if _teams.teams.is_empty() {
+            return;
+        }
+
+        if let Command::Team(name) = cmd {
+            _teams.join(&user, &name);
+        } else if let Some(button) = cmd.action() {
+            if _teams.press(&user, button, self.config.mode) {
+                self.tx_decision
+                    .send(VoteSystemUpdate::Decision(Command::Action(button)))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn tick(&self) {
+        self.teams.lock().unwrap().rotate(&self.config);
+    }
+
+    fn snapshot(&self) -> VoteSystemSnapshot {
+        VoteSystemSnapshot::Teams(self.teams.lock().unwrap().state(&self.config))
+    }
+}
+
+pub struct TeamsCreator {
+    pub config: TeamsConfig,
+}
+
+impl VoteSystemCreator for TeamsCreator {
+    fn create(&self, tx_decision: VoteSystemUpdateSender, clock: SharedClock) -> VoteFunction {
+        Box::new(_Teams::new(tx_decision, self.config.clone(), clock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+
+    fn config(mode: TeamsMode) -> TeamsConfig {
+        TeamsConfig {
+            mode,
+            turn_secs: 10,
+            ..TeamsConfig::default()
+        }
+    }
+
+    fn teams(clock: &Arc<ManualClock>, config: &TeamsConfig) -> Teams {
+        let mut teams = Teams::new(config, clock.clone());
+        teams.join(&"alice".to_owned(), "Red");
+        teams.join(&"bob".to_owned(), "blue");
+
+        teams
+    }
+
+    #[test]
+    fn teams_only_press_their_own_buttons() {
+        let clock = Arc::new(ManualClock::new());
+        let config = config(TeamsMode::Buttons);
+        let mut teams = teams(&clock, &config);
+        let alice = "alice".to_owned();
+
+        assert!(teams.press(&alice, Button::Up, config.mode));
+        assert!(teams.press(&alice, Button::Down, config.mode));
+        assert!(!teams.press(&alice, Button::A, config.mode));
+        assert!(teams.press(&"bob".to_owned(), Button::A, config.mode));
+
+        let someone = "someone".to_owned();
+        let team = teams.team_of(&someone);
+        assert_eq!(teams.team_of(&someone), team);
+
+        let state = teams.state(&config);
+        assert_eq!(state.teams[0].members.first(), Some(&alice));
+        assert_eq!(state.teams[0].inputs, 3);
+        assert_eq!(state.teams[1].inputs, 1);
+        assert_eq!(state.teams[team].members.last(), Some(&someone));
+
+        // Red pressed one more button this round.
+        clock.advance(Duration::from_secs(10));
+        teams.rotate(&config);
+        let scores: Vec<u64> = teams.state(&config).teams.iter().map(|t| t.score).collect();
+        assert_eq!(scores, vec![1, 0]);
+    }
+
+    #[test]
+    fn teams_take_turns_and_the_busiest_scores() {
+        let clock = Arc::new(ManualClock::new());
+        let config = config(TeamsMode::Turns);
+        let mut teams = teams(&clock, &config);
+        let (alice, bob) = ("alice".to_owned(), "bob".to_owned());
+
+        assert_eq!(teams.state(&config).active_team, Some("red".to_owned()));
+        assert!(!teams.press(&bob, Button::A, config.mode));
+        assert!(teams.press(&alice, Button::A, config.mode));
+
+        clock.advance(Duration::from_secs(10));
+        teams.rotate(&config);
+        assert_eq!(teams.state(&config).active_team, Some("blue".to_owned()));
+        assert!(!teams.press(&alice, Button::A, config.mode));
+        for _ in 0..2 {
+            assert!(teams.press(&bob, Button::B, config.mode));
+        }
+
+        clock.advance(Duration::from_secs(10));
+        teams.rotate(&config);
+        let state = teams.state(&config);
+        assert_eq!(state.active_team, Some("red".to_owned()));
+        assert_eq!(state.teams[0].score, 0);
+        assert_eq!(state.teams[1].score, 1);
+    }
+
+    #[test]
+    fn refuses_unknown_buttons() {
+        let team = serde_json::from_str::<TeamConfig>(r#"{"name": "red", "buttons": ["jump"]}"#);
+        assert!(team.is_err());
+    }
+}
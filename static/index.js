@@ -4,16 +4,46 @@ function onPageLoaded() {
         data: {
             renderer_data: {
                 last_inputs: [],
+                last_dropped_inputs: [],
+                flagged_users: [],
+                last_filter_counters: [],
+                last_connection_state: null,
+                last_input_latency: null,
                 last_vote_system: null,
-                last_vote_system_percentage: null,
-                last_vote_system_partial_results: null,
-                last_vote_system_elapsed_time: null
+                last_vote_system_votes: [],
+                last_vote_system_lock: null,
+                last_vote_system_snapshot: null,
+                last_vote_system_round_cancelled: null,
+                last_idle_status: null,
+                last_modifiers: [],
+                last_pause_status: null
             }
         },
         filters: {
             whenNull: function(value, or) {
                 return value !== null ? value : or;
             }
+        },
+        methods: {
+            // The snapshot of the active vote system, if it's of the given kind.
+            snapshotOf: function(kind) {
+                let snapshot = this.renderer_data.last_vote_system_snapshot;
+
+                return snapshot !== null && snapshot.kind === kind ? snapshot : null;
+            },
+            secsUntil: function(timestamp) {
+                return Math.max(0, Math.round(timestamp - Date.now() / 1000));
+            },
+            secsSince: function(timestamp) {
+                return Math.max(0, Math.round(Date.now() / 1000 - timestamp));
+            },
+            // Without any votes, every vote system gets an equal share of the meter.
+            votePercentage: function(entry) {
+                let votes = this.renderer_data.last_vote_system_votes;
+                let total = votes.reduce((sum, e) => sum + e[1], 0);
+
+                return total > 0 ? 100 * entry[1] / total : 100 / votes.length;
+            }
         }
     });
 